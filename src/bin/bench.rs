@@ -0,0 +1,139 @@
+use advent_of_code_2019::intcode::{
+    read_program, CompiledProgram, Computer, IntCodeError, IntCodeErrorKind, Program, Value,
+};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+type Machine = Computer<VecDeque<Value>, Vec<Value>>;
+
+const RUNS: usize = 5;
+
+/// How to execute the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Engine {
+    Uncached,
+    Cached,
+    Compiled,
+}
+
+struct Workload {
+    name: &'static str,
+    input_path: &'static str,
+    run: fn(&Program, Engine) -> Result<()>,
+}
+
+const WORKLOADS: [Workload; 3] = [
+    Workload {
+        name: "day9 BOOST sensor",
+        input_path: "inputs/day9.txt",
+        run: boost,
+    },
+    Workload {
+        name: "day19 50x50 scan",
+        input_path: "inputs/day19.txt",
+        run: scan,
+    },
+    Workload {
+        name: "day23 single NIC",
+        input_path: "inputs/day23.txt",
+        run: nic,
+    },
+];
+
+/// Compare the interpreter with and without the instruction cache, and the
+/// compiled engine. Speedups are relative to the uncached interpreter. Build
+/// with `--release` for meaningful numbers.
+fn main() -> Result<()> {
+    println!(
+        "{:<20} {:>12} {:>20} {:>20}",
+        "workload", "uncached", "cached", "compiled"
+    );
+
+    for workload in &WORKLOADS {
+        let input = std::fs::read_to_string(workload.input_path)?;
+        let program = read_program(&input)?;
+
+        let uncached = time(|| (workload.run)(&program, Engine::Uncached))?;
+        let cached = time(|| (workload.run)(&program, Engine::Cached))?;
+        let compiled = time(|| (workload.run)(&program, Engine::Compiled))?;
+        println!(
+            "{:<20} {:>10.2}ms {:>10.2}ms ({:>5.2}x) {:>10.2}ms ({:>5.2}x)",
+            workload.name,
+            uncached.as_secs_f64() * 1000.0,
+            cached.as_secs_f64() * 1000.0,
+            uncached.as_secs_f64() / cached.as_secs_f64(),
+            compiled.as_secs_f64() * 1000.0,
+            uncached.as_secs_f64() / compiled.as_secs_f64()
+        );
+    }
+
+    Ok(())
+}
+
+/// Best time out of a few runs.
+fn time<F: FnMut() -> Result<()>>(mut f: F) -> Result<Duration> {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        f()?;
+        best = best.min(start.elapsed());
+    }
+
+    Ok(best)
+}
+
+fn machine(program: &Program, engine: Engine) -> Machine {
+    let mut computer = Computer::with_io(VecDeque::new(), Vec::new());
+    computer
+        .set_instruction_cache(engine != Engine::Uncached)
+        .load_program(program.clone());
+    computer
+}
+
+fn execute(
+    computer: &mut Machine,
+    compiled: &CompiledProgram<VecDeque<Value>, Vec<Value>>,
+    engine: Engine,
+) -> std::result::Result<(), IntCodeError> {
+    match engine {
+        Engine::Compiled => compiled.execute(computer),
+        _ => computer.execute(),
+    }
+}
+
+fn boost(program: &Program, engine: Engine) -> Result<()> {
+    let compiled = CompiledProgram::new(program);
+    let mut computer = machine(program, engine);
+    computer.input_device_mut().push_back(2);
+    Ok(execute(&mut computer, &compiled, engine)?)
+}
+
+fn scan(program: &Program, engine: Engine) -> Result<()> {
+    let compiled = CompiledProgram::new(program);
+    let mut computer = machine(program, engine);
+    for x in 0..50 {
+        for y in 0..50 {
+            computer.input_device_mut().extend(&[x, y]);
+            computer.load_program(program.clone());
+            execute(&mut computer, &compiled, engine)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Boot a network interface and feed it a long list of empty packets, until
+/// it runs out of input.
+fn nic(program: &Program, engine: Engine) -> Result<()> {
+    let compiled = CompiledProgram::new(program);
+    let mut computer = machine(program, engine);
+    computer.input_device_mut().push_back(0);
+    computer
+        .input_device_mut()
+        .extend(std::iter::repeat_n(-1, 100_000));
+    match execute(&mut computer, &compiled, engine) {
+        Err(error) if matches!(error.kind(), IntCodeErrorKind::ReadError(_)) => Ok(()),
+        result => Ok(result?),
+    }
+}
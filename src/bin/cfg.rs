@@ -0,0 +1,14 @@
+use advent_of_code_2019::intcode::{cfg::Cfg, read_program};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Print the control-flow graph of the intcode program in the given file, in
+/// Graphviz DOT format.
+fn main() -> Result<()> {
+    let path = std::env::args().nth(1).ok_or("Usage: cfg <input file>")?;
+    let input = std::fs::read_to_string(path)?;
+    let program = read_program(&input)?;
+
+    print!("{}", Cfg::new(&program).to_dot());
+    Ok(())
+}
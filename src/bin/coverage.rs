@@ -0,0 +1,53 @@
+use advent_of_code_2019::intcode::{read_program, Computer, Coverage, IntCodeErrorKind, Value};
+use std::collections::VecDeque;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+const USAGE: &str = "Usage: coverage <program file> [--ascii] [input file...]";
+
+/// Run the intcode program in the given file once per input file, then print
+/// its disassembly annotated with the instructions executed by all the runs.
+/// Inputs are integers separated by commas or whitespace, or text with
+/// `--ascii`. Without input files, the program runs once without input.
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().ok_or(USAGE)?;
+    let mut ascii = false;
+    let mut inputs = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--ascii" => ascii = true,
+            _ => inputs.push(std::fs::read_to_string(arg)?),
+        }
+    }
+
+    if inputs.is_empty() {
+        inputs.push(String::new());
+    }
+
+    let program = read_program(&std::fs::read_to_string(path)?)?;
+    let mut coverage = Coverage::default();
+    for text in inputs {
+        let input: VecDeque<Value> = if ascii {
+            text.bytes().map(Value::from).collect()
+        } else {
+            text.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|word| !word.is_empty())
+                .map(str::parse)
+                .collect::<std::result::Result<_, _>>()?
+        };
+
+        let mut computer = Computer::with_io(input, Vec::new());
+        computer.set_coverage(true).load_program(program.clone());
+        match computer.execute() {
+            // Running out of input is the end of the run.
+            Err(error) if matches!(error.kind(), IntCodeErrorKind::ReadError(_)) => (),
+            result => result?,
+        }
+
+        coverage.merge(computer.coverage().unwrap());
+    }
+
+    print!("{}", coverage.annotate(&program));
+    Ok(())
+}
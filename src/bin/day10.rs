@@ -75,18 +75,19 @@ impl Direction {
 /// Implement clockwise ordering.
 impl PartialOrd for Direction {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Direction {
+    fn cmp(&self, other: &Self) -> Ordering {
         self.to_radians()
             .partial_cmp(&other.to_radians())
             .map(|ord| {
                 ord.reverse()
                     .then(self.squared_distance().cmp(&other.squared_distance()))
             })
-    }
-}
-
-impl Ord for Direction {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+            .unwrap()
     }
 }
 
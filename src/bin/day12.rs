@@ -121,7 +121,7 @@ impl Simulator {
     }
 }
 
-impl<'a> Iterator for Simulator {
+impl Iterator for Simulator {
     type Item = Vec<Moon>;
 
     fn next(&mut self) -> Option<Self::Item> {
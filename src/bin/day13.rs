@@ -1,6 +1,5 @@
-use advent_of_code_2019::intcode::{read_program, Computer, IntCodeError, Program};
+use advent_of_code_2019::intcode::{read_program, Computer, Program, Status};
 use std::collections::HashMap;
-use std::sync::mpsc::{self, TryRecvError};
 
 const INPUT_PATH: &str = "inputs/day13.txt";
 
@@ -52,21 +51,23 @@ fn main() -> Result<()> {
 
 /// Start the game. How many block tiles are on the screen when the game exits?
 fn part1(program: Program) -> Result<()> {
-    let (_, r1) = mpsc::channel();
-    let (t2, r2) = mpsc::channel();
-
-    std::thread::spawn(move || -> std::result::Result<(), IntCodeError> {
-        Computer::with_mpsc(r1, t2)
-            .load_program(program)
-            .execute()?;
-        Ok(())
-    });
-    let mut screen = HashMap::new();
+    let (mut computer, ..) = Computer::new();
+    computer.load_program(program);
 
-    while let Ok(x) = r2.recv() {
-        let y = r2.recv()?;
-        let tile = Tile::from(r2.recv()?);
-        screen.insert((x, y), tile);
+    let mut screen = HashMap::new();
+    let mut buffer = Vec::new();
+    loop {
+        match computer.run()? {
+            Status::Output(value) => {
+                buffer.push(value);
+                if let [x, y, tile] = buffer[..] {
+                    screen.insert((x, y), Tile::from(tile));
+                    buffer.clear();
+                }
+            }
+            Status::Halted => break,
+            status => return Err(format!("Unexpected status: {:?}", status).into()),
+        }
     }
 
     let part1 = screen.values().filter(|&&tile| tile == Tile::Block).count();
@@ -78,31 +79,23 @@ fn part1(program: Program) -> Result<()> {
 
 /// Beat the game by breaking all the blocks. What is your score after the last
 /// block is broken?
-fn part2(program: Program) -> Result<()> {
-    let (t1, r1) = mpsc::channel();
-    let (t2, r2) = mpsc::channel();
-
-    std::thread::spawn(move || -> std::result::Result<(), IntCodeError> {
-        let mut program = program;
-        program[0] = 2;
-        Computer::with_mpsc(r1, t2)
-            .load_program(program)
-            .execute()?;
-        Ok(())
-    });
-
-    // To make sure the program is ready for input.
-    std::thread::sleep(std::time::Duration::from_millis(10));
+fn part2(mut program: Program) -> Result<()> {
+    program[0] = 2;
+    let (mut computer, tx, _) = Computer::new();
+    computer.load_program(program);
 
     let mut screen = HashMap::new();
+    let mut buffer = Vec::new();
     loop {
-        match r2.try_recv() {
-            Ok(x) => {
-                let y = r2.recv()?;
-                let tile = Tile::from(r2.recv()?);
-                screen.insert((x, y), tile);
+        match computer.run()? {
+            Status::Output(value) => {
+                buffer.push(value);
+                if let [x, y, tile] = buffer[..] {
+                    screen.insert((x, y), Tile::from(tile));
+                    buffer.clear();
+                }
             }
-            Err(TryRecvError::Empty) => {
+            Status::NeedsInput => {
                 let ball_x = screen
                     .iter()
                     .find_map(|((x, _), &tile)| if tile == Tile::Ball { Some(*x) } else { None })
@@ -113,10 +106,9 @@ fn part2(program: Program) -> Result<()> {
                     .find_map(|((x, _), &tile)| if tile == Tile::Paddle { Some(*x) } else { None })
                     .unwrap();
 
-                t1.send((ball_x - paddle_x).signum())?;
+                tx.send((ball_x - paddle_x).signum())?;
 
-                // Print out the screen. Alternatively, we would need to wait a
-                // bit for the arcade to start sending new output.
+                // Print out the screen.
                 let (max_x, max_y) = screen
                     .keys()
                     .fold((0, 0), |acc, key| (acc.0.max(key.0), acc.1.max(key.1)));
@@ -128,7 +120,8 @@ fn part2(program: Program) -> Result<()> {
                     println!("{}", line);
                 }
             }
-            Err(TryRecvError::Disconnected) => break,
+            Status::Halted => break,
+            Status::Running => unreachable!(),
         }
     }
 
@@ -73,7 +73,7 @@ impl ReactionList {
 
     fn produce(&self, name: &str, amount: u64) -> impl Iterator<Item = Input> + '_ {
         let Reaction { output, inputs } = self.0.get(name).unwrap();
-        let ratio = amount / output.qty + if amount % output.qty != 0 { 1 } else { 0 };
+        let ratio = amount.div_ceil(output.qty);
 
         inputs.iter().map(move |input| input * ratio)
     }
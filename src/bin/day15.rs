@@ -1,6 +1,6 @@
-use advent_of_code_2019::intcode::{read_program, Computer, IntCodeError, Program, Value};
+use advent_of_code_2019::intcode::{self, read_program, Computer, Program, Value};
 use std::cmp::Reverse;
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 
 const INPUT_PATH: &str = "inputs/day15.txt";
 const MOVEMENTS: [Movement; 4] = [
@@ -22,19 +22,6 @@ enum Movement {
     East = 4,
 }
 
-impl std::ops::Neg for Movement {
-    type Output = Self;
-
-    fn neg(self) -> Self::Output {
-        match self {
-            Movement::North => Movement::South,
-            Movement::South => Movement::North,
-            Movement::West => Movement::East,
-            Movement::East => Movement::West,
-        }
-    }
-}
-
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Position(isize, isize);
 
@@ -80,49 +67,38 @@ fn main() -> Result<()> {
 }
 
 fn map(program: Program) -> Result<SystemMap> {
-    let (computer, tx, rx) = Computer::new();
-
-    std::thread::spawn(move || -> std::result::Result<(), IntCodeError> {
-        let mut computer = computer;
-        computer.load_program(program).execute()?;
-        Ok(())
-    });
+    let mut computer = Computer::with_io(VecDeque::new(), Vec::new());
+    computer.load_program(program);
 
     let mut map = SystemMap::new();
-    let mut position = Position::default();
-    map.insert(position, Status::Open);
-    let mut movements = Vec::new();
-
-    loop {
-        // Find an open direction. If none (`None`) can be found, backtrack.
-        if let Some(&d) = MOVEMENTS
-            .iter()
-            .find(|&&d| map.get(&(position + d)).is_none())
-        {
-            tx.send(d as Value)?;
-            match Status::from(rx.recv()?) {
-                Status::Wall => {
-                    map.insert(position + d, Status::Wall);
-                }
-                x => {
-                    position = position + d;
-                    movements.push(d);
-                    map.insert(position, x);
-                }
+    map.insert(Position::default(), Status::Open);
+
+    // Explore every position reached by forking the droid, instead of moving
+    // it back and forth.
+    let mut queue = VecDeque::new();
+    queue.push_back((Position::default(), computer.snapshot()));
+
+    while let Some((position, snapshot)) = queue.pop_front() {
+        for &d in MOVEMENTS.iter() {
+            let new_position = position + d;
+            if map.contains_key(&new_position) {
+                continue;
             }
-        } else {
-            if let Some(d) = movements.pop() {
-                tx.send(-d as Value)?;
-                position = position + (-d);
-                // Remember to eat the output (which is `Status::Open`).
-                rx.recv()?;
-            } else {
-                break;
+
+            let mut droid = snapshot.restore();
+            droid.input_device_mut().push_back(d as Value);
+            let status = match droid.run()? {
+                intcode::Status::Output(value) => Status::from(value),
+                status => return Err(format!("Unexpected status: {:?}", status).into()),
+            };
+
+            map.insert(new_position, status);
+            if status != Status::Wall {
+                queue.push_back((new_position, droid.snapshot()));
             }
         }
     }
 
-    tx.send(0)?;
     Ok(map)
 }
 
@@ -1,4 +1,4 @@
-use std::iter::repeat;
+use std::iter::repeat_n;
 
 const INPUT_PATH: &str = "inputs/day16.txt";
 
@@ -28,11 +28,10 @@ impl Iterator for Signal {
 }
 
 fn fft_row(idx: usize) -> impl Iterator<Item = i32> {
-    repeat(0)
-        .take(idx)
-        .chain(repeat(1).take(idx))
-        .chain(repeat(0).take(idx))
-        .chain(repeat(-1).take(idx))
+    repeat_n(0, idx)
+        .chain(repeat_n(1, idx))
+        .chain(repeat_n(0, idx))
+        .chain(repeat_n(-1, idx))
         .cycle()
         .skip(1)
 }
@@ -75,8 +74,7 @@ fn part2(digits: &[i32]) {
 
     // We can ignore the digits before the offset. Starting at the end to
     // accumulate.
-    let mut signal: Vec<i32> = repeat(digits.iter())
-        .take(10_000)
+    let mut signal: Vec<i32> = repeat_n(digits.iter(), 10_000)
         .flatten()
         .skip(offset)
         .copied()
@@ -1,9 +1,7 @@
-use advent_of_code_2019::intcode::{read_program, Computer, Program, Value};
+use advent_of_code_2019::intcode::{read_program, AsciiComputer, Program, Value};
 use std::collections::HashMap;
-use std::sync::mpsc::Receiver;
 
 const INPUT_PATH: &str = "inputs/day17.txt";
-const SCAFFOLD: isize = '#' as isize;
 const OPEN: isize = '.' as isize;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
@@ -47,22 +45,21 @@ impl Map {
         self.0.get(&position).copied()
     }
 
-    fn iter(&self) -> std::collections::hash_map::Iter<Position, Value> {
+    fn iter(&self) -> std::collections::hash_map::Iter<'_, Position, Value> {
         self.0.iter()
     }
 }
 
-impl From<&Receiver<Value>> for Map {
-    fn from(rx: &Receiver<Value>) -> Self {
+impl From<&str> for Map {
+    fn from(text: &str) -> Self {
         let mut position = Position::default();
         let mut map = HashMap::new();
 
-        for value in rx.try_iter() {
-            // New line.
-            if value == 10 {
+        for c in text.chars() {
+            if c == '\n' {
                 position = Position(0, position.1 + 1);
             } else {
-                map.insert(position, value);
+                map.insert(position, c as Value);
                 position = position + Direction::Right;
             }
         }
@@ -83,10 +80,8 @@ fn main() -> Result<()> {
 /// Run your ASCII program. What is the sum of the alignment parameters for the
 /// scaffold intersections?
 fn part1(program: Program) -> Result<()> {
-    let (mut computer, _, rx) = Computer::new();
-    computer.load_program(program).execute()?;
-
-    let map = Map::from(&rx);
+    let mut computer = AsciiComputer::new(program);
+    let map = Map::from(computer.read_until_prompt()?.as_str());
     let part1: isize = map.iter()
         .filter_map(|(&pos, &value)|
             // Use `!= OPEN`, since the bot is on a scaffold.
@@ -112,25 +107,19 @@ fn part2(mut program: Program) -> Result<()> {
     // program at address 0 from 1 to 2.
     program[0] = 2;
 
-    let (mut computer, tx, rx) = Computer::new();
+    let mut computer = AsciiComputer::new(program);
 
     // Working it out by hand, we get:
-    let main = "A,C,A,C,B,C,B,A,C,B";
-    let fn_a = "R,4,R,10,R,8,R,4";
-    let fn_b = "R,4,L,12,R,6,L,12";
-    let fn_c = "R,10,R,6,R,4";
-
-    for c in format!("{}\n{}\n{}\n{}\n", main, fn_a, fn_b, fn_c).chars() {
-        tx.send(c as isize)?;
-    }
+    computer.send_line("A,C,A,C,B,C,B,A,C,B");
+    computer.send_line("R,4,R,10,R,8,R,4");
+    computer.send_line("R,4,L,12,R,6,L,12");
+    computer.send_line("R,10,R,6,R,4");
 
     // We don't want the continuous video feed.
-    tx.send('n' as isize)?;
-    tx.send('\n' as isize)?;
-
-    computer.load_program(program).execute()?;
+    computer.send_line("n");
 
-    let part2 = rx.try_iter().last().unwrap();
+    computer.read_until_prompt()?;
+    let part2 = *computer.results().last().ok_or("No dust reported")?;
     println!("Part 2: {}", part2);
     Ok(())
 }
@@ -1,4 +1,5 @@
 use advent_of_code_2019::intcode::{read_program, Computer, Program};
+use std::collections::VecDeque;
 
 const INPUT_PATH: &str = "inputs/day19.txt";
 
@@ -16,17 +17,16 @@ fn main() -> Result<()> {
 /// How many points are affected by the tractor beam in the 50x50 area closest
 /// to the emitter?
 fn part1(program: Program) -> Result<()> {
-    let (mut computer, tx, rx) = Computer::new();
+    let mut computer = Computer::with_io(VecDeque::new(), Vec::new());
 
     for x in 0..50 {
         for y in 0..50 {
-            tx.send(x)?;
-            tx.send(y)?;
+            computer.input_device_mut().extend(&[x, y]);
             computer.load_program(program.clone()).execute()?;
         }
     }
 
-    let part1: isize = rx.try_iter().sum();
+    let part1: isize = computer.output_device().iter().sum();
     println!("Part 1: {}", part1);
 
     Ok(())
@@ -42,20 +42,18 @@ fn part2(program: Program) -> Result<()> {
     // point is (x, y), the lower-left point is (x - 99, y + 99). So, if both
     // are within the tractor beam, the top-left point will be (x - 99, y).
     for x in 100.. {
-        let (mut computer, tx, rx) = Computer::new();
+        let mut computer = Computer::with_io(VecDeque::new(), Vec::new());
         let first_y = (0..)
             .find(|&y| {
-                tx.send(x).unwrap();
-                tx.send(y).unwrap();
+                computer.input_device_mut().extend(&[x, y]);
                 computer.load_program(program.clone()).execute().unwrap();
-                rx.recv().unwrap() == 1
+                computer.output_device_mut().pop() == Some(1)
             })
             .unwrap();
 
-        tx.send(x - 99)?;
-        tx.send(first_y + 99)?;
+        computer.input_device_mut().extend(&[x - 99, first_y + 99]);
         computer.load_program(program.clone()).execute()?;
-        if rx.recv()? == 1 {
+        if computer.output_device_mut().pop() == Some(1) {
             let part2 = (x - 99) * 10_000 + first_y;
             println!("Part 2: {}", part2);
             break;
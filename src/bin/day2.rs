@@ -1,8 +1,10 @@
-use advent_of_code_2019::intcode::{read_program, Computer, Program};
+use advent_of_code_2019::intcode::{read_program, Computer, IntCodeErrorKind, Program};
+use std::collections::VecDeque;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 const INPUT_PATH: &str = "inputs/day2.txt";
+const INSTRUCTION_LIMIT: u64 = 10_000;
 
 fn main() -> Result<()> {
     let input = std::fs::read_to_string(INPUT_PATH)?;
@@ -21,11 +23,11 @@ fn part1(mut program: Program) -> Result<()> {
     program[1] = 12;
     program[2] = 2;
 
-    let (mut computer, ..) = Computer::new();
+    let mut computer = Computer::with_io(VecDeque::new(), Vec::new());
     computer.load_program(program);
     computer.execute()?;
 
-    let part1 = computer.memory().first().unwrap();
+    let part1 = computer.memory().get(0);
 
     println!("Part 1: {}", part1);
     Ok(())
@@ -35,16 +37,24 @@ fn part1(mut program: Program) -> Result<()> {
 /// 19690720. What is 100 * noun + verb? (For example, if noun = 12 and
 /// verb = 2, the answer would be 1202.)
 fn part2(program: Program) -> Result<()> {
-    let (mut computer, ..) = Computer::new();
+    let mut computer = Computer::with_io(VecDeque::new(), Vec::new());
+    computer.set_instruction_limit(Some(INSTRUCTION_LIMIT));
 
     for noun in 0..100 {
         for verb in 0..100 {
             let mut new_program = program.to_owned();
             new_program[1] = noun;
             new_program[2] = verb;
-            computer.load_program(new_program).execute()?;
+            match computer.load_program(new_program).execute() {
+                Ok(()) => (),
+                // Some candidates loop forever: skip them.
+                Err(error) if matches!(error.kind(), IntCodeErrorKind::InstructionLimit(_)) => {
+                    continue
+                }
+                Err(error) => return Err(error.into()),
+            }
 
-            if computer.memory().first() == Some(&19_690_720) {
+            if computer.memory().get(0) == 19_690_720 {
                 println!("Part 2: {}", noun * 100 + verb);
                 break;
             }
@@ -1,4 +1,4 @@
-use advent_of_code_2019::intcode::{read_program, Computer, IntCodeError, Program, Value};
+use advent_of_code_2019::intcode::{read_program, AsciiComputer, Program};
 
 const INPUT_PATH: &str = "inputs/day21.txt";
 
@@ -16,90 +16,55 @@ fn main() -> Result<()> {
 /// Program the springdroid with logic that allows it to survey the hull without
 /// falling into space. What amount of hull damage does it report?
 fn part1(program: Program) -> Result<()> {
-    let (computer, tx, rx) = Computer::new();
-    let send = |instruction: &str| send_instruction(instruction, tx.clone());
-
-    std::thread::spawn(move || -> std::result::Result<(), IntCodeError> {
-        let mut computer = computer;
-        computer.load_program(program).execute()?;
-        Ok(())
-    });
+    let mut computer = AsciiComputer::new(program);
 
     // Jump is there is no ground in front.
-    send("NOT A J")?;
+    computer.send_line("NOT A J");
 
     // Or if there is a hole coming up.
-    send("NOT C T")?;
-    send("OR T J")?;
+    computer.send_line("NOT C T");
+    computer.send_line("OR T J");
 
     // As long as there is ground to land on.
-    send("AND D J")?;
+    computer.send_line("AND D J");
 
     // Start walking.
-    send("WALK")?;
-
-    let result: Vec<Value> = rx.iter().collect();
-    let part1 = *result.last().unwrap();
-    if part1 > std::u8::MAX as isize {
-        println!("Part 1: {}", part1);
-    } else {
-        let s: String = result
-            .into_iter()
-            .map(|value| (value as u8) as char)
-            .collect();
-        println!("{}", s);
+    computer.send_line("WALK");
+
+    let output = computer.read_until_prompt()?;
+    match computer.results().last() {
+        Some(part1) => println!("Part 1: {}", part1),
+        None => println!("{}", output),
     }
 
     Ok(())
 }
 
 fn part2(program: Program) -> Result<()> {
-    let (computer, tx, rx) = Computer::new();
-    let send = |instruction: &str| send_instruction(instruction, tx.clone());
-
-    std::thread::spawn(move || -> std::result::Result<(), IntCodeError> {
-        let mut computer = computer;
-        computer.load_program(program).execute()?;
-        Ok(())
-    });
+    let mut computer = AsciiComputer::new(program);
 
     // Jump is there is a hole coming soon.
-    send("OR A J")?;
-    send("AND B J")?;
-    send("AND C J")?;
-    send("NOT J J")?;
+    computer.send_line("OR A J");
+    computer.send_line("AND B J");
+    computer.send_line("AND C J");
+    computer.send_line("NOT J J");
 
     // As long as there is ground to land on.
-    send("AND D J")?;
+    computer.send_line("AND D J");
 
     // As long as we can step forward or jump afterwards.
-    send("OR E T")?;
-    send("OR H T")?;
-    send("AND T J")?;
+    computer.send_line("OR E T");
+    computer.send_line("OR H T");
+    computer.send_line("AND T J");
 
     // Start running.
-    send("RUN")?;
-
-    let result: Vec<Value> = rx.iter().collect();
-    let part2 = *result.last().unwrap();
-    if part2 > std::u8::MAX as isize {
-        println!("Part 2: {}", part2);
-    } else {
-        let s: String = result
-            .into_iter()
-            .map(|value| (value as u8) as char)
-            .collect();
-        println!("{}", s);
-    }
-
-    Ok(())
-}
+    computer.send_line("RUN");
 
-fn send_instruction(instruction: &str, tx: std::sync::mpsc::Sender<Value>) -> Result<()> {
-    for c in instruction.chars() {
-        tx.send(c as Value)?;
+    let output = computer.read_until_prompt()?;
+    match computer.results().last() {
+        Some(part2) => println!("Part 2: {}", part2),
+        None => println!("{}", output),
     }
 
-    tx.send('\n' as Value)?;
     Ok(())
 }
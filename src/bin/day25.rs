@@ -1,4 +1,4 @@
-use advent_of_code_2019::intcode::{read_program, Computer, IntCodeError, Program};
+use advent_of_code_2019::intcode::{read_program, AsciiComputer, Program};
 use std::io::{self, Write};
 
 const INPUT_PATH: &str = "inputs/day25.txt";
@@ -14,28 +14,22 @@ fn main() -> Result<()> {
 }
 
 fn part1(program: Program) -> Result<()> {
-    let (mut computer, tx, rx) = Computer::new();
-    computer.load_program(program);
-
-    std::thread::spawn(move || -> std::result::Result<(), IntCodeError> {
-        let mut computer = computer;
-        computer.execute()?;
-        Ok(())
-    });
+    let mut computer = AsciiComputer::new(program);
 
     loop {
-        let output: Vec<u8> = rx.try_iter().map(|value| value as u8).collect();
-        if !output.is_empty() {
-            io::stdout().write_all(&output)?;
-            continue;
+        print!("{}", computer.read_until_prompt()?);
+        if computer.is_halted() {
+            break;
         }
 
+        io::stdout().flush()?;
         let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-
-        for byte in input.bytes() {
-            tx.send(byte as isize)?;
+        if io::stdin().read_line(&mut input)? == 0 {
+            break;
         }
-        std::thread::sleep(std::time::Duration::from_millis(10));
+
+        computer.send_line(input.trim_end_matches('\n'));
     }
+
+    Ok(())
 }
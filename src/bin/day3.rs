@@ -86,7 +86,7 @@ fn into_wires(input: &str) -> Vec<Wire> {
 fn instruction(input: &str) -> impl Iterator<Item = Direction> {
     let direction = Direction::from(input.chars().next().unwrap());
     let steps = input[1..].parse::<usize>().unwrap();
-    std::iter::repeat(direction).take(steps)
+    std::iter::repeat_n(direction, steps)
 }
 
 /// What is the Manhattan distance from the central port to the closest
@@ -14,7 +14,7 @@ fn main() {
 /// - The value is within the range given in your puzzle input.
 /// - Two adjacent digits are the same (like 22 in 122345).
 /// - Going from left to right, the digits never decrease; they only ever
-/// increase or stay the same (like 111123 or 135679).
+///   increase or stay the same (like 111123 or 135679).
 fn part1(input: (u32, u32)) {
     let part1 = (input.0..=input.1)
         .filter(|&password| {
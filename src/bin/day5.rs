@@ -1,4 +1,5 @@
 use advent_of_code_2019::intcode::{read_program, Computer, Program};
+use std::collections::VecDeque;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
@@ -15,22 +16,30 @@ fn main() -> Result<()> {
 /// After providing 1 to the only input instruction and passing all the tests,
 /// what diagnostic code does the program produce?
 fn part1(program: Program) -> Result<()> {
-    let (mut computer, tx, rx) = Computer::new();
-    tx.send(1)?;
+    let mut computer = Computer::with_io(VecDeque::from(vec![1]), Vec::new());
     computer.load_program(program).execute()?;
 
-    let part1 = rx.into_iter().find(|&v| v != 0).unwrap_or(0);
+    let part1 = computer
+        .output_device()
+        .iter()
+        .copied()
+        .find(|&v| v != 0)
+        .unwrap_or(0);
     println!("Part 1: {}", part1);
     Ok(())
 }
 
 // What is the diagnostic code for system ID 5?
 fn part2(program: Program) -> Result<()> {
-    let (mut computer, tx, rx) = Computer::new();
-    tx.send(5)?;
+    let mut computer = Computer::with_io(VecDeque::from(vec![5]), Vec::new());
     computer.load_program(program).execute()?;
 
-    let part2 = rx.into_iter().find(|&v| v != 0).unwrap_or(0);
+    let part2 = computer
+        .output_device()
+        .iter()
+        .copied()
+        .find(|&v| v != 0)
+        .unwrap_or(0);
     println!("Part 2: {}", part2);
     Ok(())
 }
@@ -1,4 +1,5 @@
 use advent_of_code_2019::intcode::{read_program, Computer, Program};
+use std::collections::VecDeque;
 
 const INPUT_PATH: &str = "inputs/day9.txt";
 
@@ -16,11 +17,10 @@ fn main() -> Result<()> {
 /// output a single value, the BOOST keycode. What BOOST keycode does it
 /// produce?
 fn part1(program: Program) -> Result<()> {
-    let (mut computer, tx, rx) = Computer::new();
-    tx.send(1)?;
+    let mut computer = Computer::with_io(VecDeque::from(vec![1]), Vec::new());
     computer.load_program(program).execute()?;
 
-    let part1 = rx.recv()?;
+    let part1 = computer.output_device().first().ok_or("No output.")?;
     println!("Part 1: {}", part1);
     Ok(())
 }
@@ -34,11 +34,10 @@ fn part1(program: Program) -> Result<()> {
 /// Run the BOOST program in sensor boost mode. What are the coordinates of the
 /// distress signal?
 fn part2(program: Program) -> Result<()> {
-    let (mut computer, tx, rx) = Computer::new();
-    tx.send(2)?;
+    let mut computer = Computer::with_io(VecDeque::from(vec![2]), Vec::new());
     computer.load_program(program).execute()?;
 
-    let part2 = rx.recv()?;
+    let part2 = computer.output_device().first().ok_or("No output.")?;
     println!("Part 2: {}", part2);
     Ok(())
 }
@@ -0,0 +1,306 @@
+use advent_of_code_2019::intcode::{
+    disasm, load_snapshot, read_program, save_snapshot, Address, Computer, Status, TraceWriter,
+    Value,
+};
+use std::collections::{BTreeSet, VecDeque};
+use std::io::{self, BufRead, Write};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+type Machine = Computer<VecDeque<Value>, Vec<Value>>;
+
+const HELP: &str = "\
+Commands:
+  s, step [n]          execute n instructions (default 1)
+  c, continue          run until a breakpoint, a watchpoint, an input or a halt
+  bs, back [n]         undo n instructions (default 1), outputs are kept
+  rewind <count>       undo instructions until count of them were executed
+  who <addr>           show the last instruction that wrote to an address
+  b, break <addr>      set a breakpoint
+  d, delete <addr>     delete a breakpoint
+  w, watch <addr>      stop on writes to an address
+  u, unwatch <addr>    delete a watchpoint
+  l, list [addr] [n]   disassemble n instructions (default: 10 at the IP)
+  r, regs              show the instruction pointer and the relative base
+  x, dump <addr> [n]   show n memory values (default 1)
+  set <addr> <value>   write to memory
+  jump <addr>          move the instruction pointer
+  i, input <values>    queue input values
+  a, ascii <text>      queue a line of ASCII input
+  o, output            show every output so far
+  trace on|off         print every executed instruction
+  save <file>          save the state of the machine to a file
+  load <file>          restore the state of the machine from a file
+  q, quit              exit";
+
+#[derive(Debug, Default)]
+struct Debugger {
+    breakpoints: BTreeSet<Address>,
+    watchpoints: BTreeSet<Address>,
+}
+
+impl Debugger {
+    /// Execute one instruction, returning whether execution should stop.
+    fn step(&self, computer: &mut Machine) -> Result<bool> {
+        let ip = computer.instruction_pointer();
+        let status = computer.step()?;
+
+        if let Some((address, value)) = computer.last_write() {
+            if self.watchpoints.contains(&address) {
+                println!("Watchpoint: [{}] = {} (written at {})", address, value, ip);
+                return Ok(true);
+            }
+        }
+
+        Ok(match status {
+            Status::Running => false,
+            Status::Output(value) => {
+                computer.output_device_mut().push(value);
+                print_output(value);
+                false
+            }
+            Status::NeedsInput => {
+                println!("Waiting for input at {}.", ip);
+                true
+            }
+            Status::Halted => {
+                println!("Halted at {}.", ip);
+                true
+            }
+        })
+    }
+
+    fn run(&self, computer: &mut Machine) -> Result<()> {
+        if self.step(computer)? {
+            return Ok(());
+        }
+
+        loop {
+            let ip = computer.instruction_pointer();
+            if self.breakpoints.contains(&ip) {
+                println!("Breakpoint at {}.", ip);
+                print_instruction(computer, ip);
+                return Ok(());
+            }
+
+            if self.step(computer)? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .ok_or("Usage: debugger <input file>")?;
+    let input = std::fs::read_to_string(path)?;
+    let program = read_program(&input)?;
+
+    let mut computer = Computer::with_io(VecDeque::new(), Vec::new());
+    computer.set_history(true).load_program(program);
+    let mut debugger = Debugger::default();
+
+    println!("Type `help` for the list of commands.");
+    print_instruction(&computer, computer.instruction_pointer());
+
+    let stdin = io::stdin();
+    loop {
+        print!("(icdb) ");
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+
+        let (command, rest) = match line.trim().split_once(' ') {
+            Some((command, rest)) => (command, rest.trim()),
+            None => (line.trim(), ""),
+        };
+
+        if let Err(error) = execute(&mut debugger, &mut computer, command, rest) {
+            println!("Error: {}", error);
+        }
+
+        if let "q" | "quit" = command {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn execute(
+    debugger: &mut Debugger,
+    computer: &mut Machine,
+    command: &str,
+    rest: &str,
+) -> Result<()> {
+    let mut arguments = rest.split_whitespace().map(str::parse::<Value>);
+    let mut argument = |default: Option<Value>| -> Result<Value> {
+        match arguments.next() {
+            Some(value) => Ok(value?),
+            None => default.ok_or_else(|| "Missing argument.".into()),
+        }
+    };
+
+    match command {
+        "" | "q" | "quit" => (),
+        "h" | "help" => println!("{}", HELP),
+        "s" | "step" => {
+            for _ in 0..argument(Some(1))? {
+                let ip = computer.instruction_pointer();
+                print_instruction(computer, ip);
+                if debugger.step(computer)? {
+                    break;
+                }
+            }
+            print_instruction(computer, computer.instruction_pointer());
+        }
+        "c" | "continue" => debugger.run(computer)?,
+        "bs" | "back" => {
+            let count = computer.instruction_count();
+            let steps = argument(Some(1))? as u64;
+            computer.rewind_to(count.saturating_sub(steps))?;
+            print_instruction(computer, computer.instruction_pointer());
+        }
+        "rewind" => {
+            computer.rewind_to(argument(None)? as u64)?;
+            print_instruction(computer, computer.instruction_pointer());
+        }
+        "who" => {
+            let address = argument(None)?;
+            match computer.last_writer(address) {
+                Some(entry) => {
+                    let write = entry.write.unwrap();
+                    println!(
+                        "[{}] = {} (was {}), written by instruction {} at {}:",
+                        address,
+                        write.new,
+                        write.old,
+                        entry.instruction_count,
+                        entry.instruction_pointer
+                    );
+                    print_instruction(computer, entry.instruction_pointer);
+                }
+                None => println!(
+                    "[{}] was not written since the program was loaded.",
+                    address
+                ),
+            }
+        }
+        "b" | "break" => {
+            debugger.breakpoints.insert(argument(None)?);
+        }
+        "d" | "delete" => {
+            debugger.breakpoints.remove(&argument(None)?);
+        }
+        "w" | "watch" => {
+            debugger.watchpoints.insert(argument(None)?);
+        }
+        "u" | "unwatch" => {
+            debugger.watchpoints.remove(&argument(None)?);
+        }
+        "l" | "list" => {
+            let mut address = argument(Some(computer.instruction_pointer()))?;
+            for _ in 0..argument(Some(10))? {
+                match disasm::decode(computer.memory().as_slice(), address as usize) {
+                    Some(instruction) => {
+                        print_instruction(computer, address);
+                        address = instruction.next_address() as Address;
+                    }
+                    None => {
+                        print_instruction(computer, address);
+                        address += 1;
+                    }
+                }
+            }
+        }
+        "r" | "regs" => {
+            println!("ip = {}", computer.instruction_pointer());
+            println!("rb = {}", computer.relative_base());
+            println!("instructions = {}", computer.instruction_count());
+            println!("breakpoints = {:?}", debugger.breakpoints);
+            println!("watchpoints = {:?}", debugger.watchpoints);
+            println!("queued input = {:?}", computer.input_device());
+        }
+        "x" | "dump" => {
+            let address = argument(None)?;
+            let count = argument(Some(1))?;
+            for address in address..address + count {
+                let value = computer.memory().get(address as usize);
+                println!("[{}] = {}", address, value);
+            }
+        }
+        "set" => {
+            let address = argument(None)?;
+            let value = argument(None)?;
+            computer.write_memory(address, value)?;
+        }
+        "jump" => {
+            computer.set_instruction_pointer(argument(None)?);
+            print_instruction(computer, computer.instruction_pointer());
+        }
+        "i" | "input" => {
+            let values = rest
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|value| !value.is_empty())
+                .map(str::parse::<Value>)
+                .collect::<std::result::Result<Vec<Value>, _>>()?;
+            computer.input_device_mut().extend(values);
+        }
+        "a" | "ascii" => {
+            let values = rest.bytes().chain(Some(b'\n')).map(Value::from);
+            computer.input_device_mut().extend(values);
+        }
+        "o" | "output" => {
+            let output = computer.output_device();
+            println!("{:?}", output);
+            if output.iter().all(|&value| (0..128).contains(&value)) {
+                let text: String = output.iter().map(|&value| value as u8 as char).collect();
+                println!("{}", text);
+            }
+        }
+        "trace" => match rest {
+            "on" => {
+                computer.set_trace_sink(TraceWriter(io::stdout()));
+            }
+            "off" => {
+                computer.take_trace_sink();
+            }
+            _ => return Err("Expected `trace on` or `trace off`.".into()),
+        },
+        "save" => save_snapshot(rest, &computer.snapshot())?,
+        "load" => {
+            computer.restore(&load_snapshot(rest)?);
+            print_instruction(computer, computer.instruction_pointer());
+        }
+        _ => return Err(format!("Unknown command `{}`. Type `help` for help.", command).into()),
+    }
+
+    Ok(())
+}
+
+fn print_instruction(computer: &Machine, address: Address) {
+    let marker = if address == computer.instruction_pointer() {
+        "=>"
+    } else {
+        "  "
+    };
+
+    match disasm::decode(computer.memory().as_slice(), address as usize) {
+        Some(instruction) => println!("{} {:>5}: {}", marker, address, instruction),
+        None => {
+            let value = computer.memory().get(address as usize);
+            println!("{} {:>5}: data {}", marker, address, value);
+        }
+    }
+}
+
+fn print_output(value: Value) {
+    match value {
+        10 => println!("Output: 10 '\\n'"),
+        32..=126 => println!("Output: {} '{}'", value, value as u8 as char),
+        _ => println!("Output: {}", value),
+    }
+}
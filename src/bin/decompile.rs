@@ -0,0 +1,25 @@
+use advent_of_code_2019::intcode::{cfg::Cfg, decompile, read_program};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Print the intcode program in the given file as pseudo-Rust. Addresses given
+/// after the file are decompiled as functions too, for code only reached by
+/// indirect jumps or once an opcode was patched: `decompile inputs/day5.txt 10`
+/// finds the code day 5 runs. Jump tables are followed without them.
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let path = args
+        .next()
+        .ok_or("Usage: decompile <input file> [entry address...]")?;
+    let mut entries = vec![0];
+    for arg in args {
+        entries.push(arg.parse()?);
+    }
+
+    let input = std::fs::read_to_string(path)?;
+    let program = read_program(&input)?;
+
+    let cfg = Cfg::with_entries(&program, &entries);
+    print!("{}", decompile::decompile_cfg(&cfg));
+    Ok(())
+}
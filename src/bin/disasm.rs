@@ -0,0 +1,15 @@
+use advent_of_code_2019::intcode::{disasm, read_program};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Print an annotated listing of the intcode program in the given file.
+fn main() -> Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .ok_or("Usage: disasm <input file>")?;
+    let input = std::fs::read_to_string(path)?;
+    let program = read_program(&input)?;
+
+    print!("{}", disasm::disassemble(&program));
+    Ok(())
+}
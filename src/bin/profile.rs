@@ -0,0 +1,70 @@
+use advent_of_code_2019::intcode::{read_program, Computer, IntCodeErrorKind, Value};
+use std::collections::VecDeque;
+use std::io::Read;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+const USAGE: &str = "Usage: profile <input file> [--ascii] [--restart] [--folded <output file>]";
+const REPORT_LENGTH: usize = 20;
+
+/// Run the intcode program in the given file on the input read from stdin,
+/// then print a profile of the run to stderr. The input is integers separated
+/// by commas or whitespace, or text with `--ascii`. With `--restart`, the
+/// program is run again while input remains, as in day 19. `--folded` writes
+/// the instructions executed by call stack, for flame graph tools.
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().ok_or(USAGE)?;
+    let mut ascii = false;
+    let mut restart = false;
+    let mut folded = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ascii" => ascii = true,
+            "--restart" => restart = true,
+            "--folded" => folded = Some(args.next().ok_or(USAGE)?),
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    let program = read_program(&std::fs::read_to_string(path)?)?;
+    let mut text = String::new();
+    std::io::stdin().read_to_string(&mut text)?;
+    let input: VecDeque<Value> = if ascii {
+        text.bytes().map(Value::from).collect()
+    } else {
+        text.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|word| !word.is_empty())
+            .map(str::parse)
+            .collect::<std::result::Result<_, _>>()?
+    };
+
+    let mut computer = Computer::with_io(input, Vec::new());
+    computer.set_profiling(true);
+    loop {
+        computer.load_program(program.clone());
+        match computer.execute() {
+            // Running out of input is the end of the run.
+            Err(error) if matches!(error.kind(), IntCodeErrorKind::ReadError(_)) => break,
+            result => result?,
+        }
+
+        if !restart || computer.input_device().is_empty() {
+            break;
+        }
+    }
+
+    for &value in computer.output_device() {
+        match value {
+            0..=127 if ascii => print!("{}", value as u8 as char),
+            _ => println!("{}", value),
+        }
+    }
+
+    eprint!("{}", computer.profile().report(&program, REPORT_LENGTH));
+    if let Some(path) = folded {
+        std::fs::write(path, computer.profile().to_folded())?;
+    }
+
+    Ok(())
+}
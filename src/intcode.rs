@@ -1,12 +1,54 @@
+mod amplifier;
+mod ascii;
+pub mod asm;
+mod async_io;
+mod cache;
+pub mod cfg;
+mod compile;
+mod coverage;
+pub mod decompile;
+pub mod disasm;
+mod error;
+mod history;
+mod io;
+mod memory;
+mod network;
+mod profile;
+mod scheduler;
+mod self_modification;
+mod snapshot;
+mod trace;
+mod word;
+
+pub use amplifier::Amplifiers;
+pub use ascii::AsciiComputer;
+pub use async_io::{async_channel, AsyncInput, AsyncReceiver, AsyncSender};
+pub use compile::CompiledProgram;
+pub use coverage::Coverage;
+pub use error::{ErrorContext, IntCodeError, IntCodeErrorKind};
+pub use history::HistoryEntry;
+pub use io::{Input, InputFn, InputIter, Output, OutputFn};
+pub use memory::{Memory, PAGE_SIZE};
+pub use network::{LastPacketNat, Nat, Network, Packet, NAT_ADDRESS};
+pub use profile::{BranchCount, Profile};
+pub use scheduler::{Policy, RoundRobin, ScheduleEvent, Scheduler, SeededRandom, Switch};
+pub use self_modification::CodeWrite;
+pub use snapshot::{load_snapshot, save_snapshot, Snapshot, SnapshotError, SNAPSHOT_VERSION};
 use std::convert::TryFrom;
 use std::sync::mpsc;
+use std::time::{Duration, Instant};
+pub use trace::{MemoryWrite, TraceFn, TraceRecord, TraceSink, TraceWriter, TracedOperand};
+pub use word::Word;
 
 pub type Address = isize;
-pub type Memory = Vec<isize>;
 type Parameters = (ParameterMode, ParameterMode, ParameterMode);
 pub type Program = Vec<isize>;
 pub type Value = isize;
 
+/// The deadline is only checked every so many instructions, as reading the
+/// clock is comparatively slow.
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
 pub fn read_program(text: &str) -> Result<Program, std::num::ParseIntError> {
     let mut program = Vec::new();
     for value in text.trim().split(',') {
@@ -16,28 +58,15 @@ pub fn read_program(text: &str) -> Result<Program, std::num::ParseIntError> {
     Ok(program)
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum IntCodeError {
-    InvalidAddress(Address),
-    InvalidOpCode(Value),
-    InvalidParameterMode(Value),
-    ReadError,
-    WriteError,
-    WriteImmediateMode,
-}
-
-impl std::fmt::Display for IntCodeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        write!(f, "{:?}", self)
-    }
+pub fn write_program(program: &[Value]) -> String {
+    let values: Vec<String> = program.iter().map(Value::to_string).collect();
+    values.join(",")
 }
 
-impl std::error::Error for IntCodeError {}
-
 type IntCodeResult<T> = std::result::Result<T, IntCodeError>;
 
-#[derive(Debug, Clone, Copy)]
-enum Opcode {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode {
     Add,
     Multiply,
     Input,
@@ -50,6 +79,60 @@ enum Opcode {
     Halt,
 }
 
+impl Opcode {
+    pub const ALL: [Opcode; 10] = [
+        Opcode::Add,
+        Opcode::Multiply,
+        Opcode::Input,
+        Opcode::Output,
+        Opcode::JumpIfTrue,
+        Opcode::JumpIfFalse,
+        Opcode::LessThan,
+        Opcode::Equals,
+        Opcode::RelativeBaseOffset,
+        Opcode::Halt,
+    ];
+
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Opcode> {
+        Opcode::ALL
+            .iter()
+            .copied()
+            .find(|opcode| opcode.mnemonic() == mnemonic)
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Opcode::Add => "add",
+            Opcode::Multiply => "mul",
+            Opcode::Input => "in",
+            Opcode::Output => "out",
+            Opcode::JumpIfTrue => "jt",
+            Opcode::JumpIfFalse => "jf",
+            Opcode::LessThan => "lt",
+            Opcode::Equals => "eq",
+            Opcode::RelativeBaseOffset => "arb",
+            Opcode::Halt => "hlt",
+        }
+    }
+
+    pub fn parameter_count(self) -> usize {
+        match self {
+            Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => 3,
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => 2,
+            Opcode::Input | Opcode::Output | Opcode::RelativeBaseOffset => 1,
+            Opcode::Halt => 0,
+        }
+    }
+
+    /// Whether the last parameter is the address written to.
+    pub fn writes_memory(self) -> bool {
+        matches!(
+            self,
+            Opcode::Add | Opcode::Multiply | Opcode::Input | Opcode::LessThan | Opcode::Equals
+        )
+    }
+}
+
 impl TryFrom<Value> for Opcode {
     type Error = IntCodeError;
 
@@ -65,13 +148,30 @@ impl TryFrom<Value> for Opcode {
             8 => Opcode::Equals,
             9 => Opcode::RelativeBaseOffset,
             99 => Opcode::Halt,
-            _ => return Err(IntCodeError::InvalidOpCode(value)),
+            _ => return Err(IntCodeErrorKind::InvalidOpCode(value).into()),
         })
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum ParameterMode {
+impl From<Opcode> for Value {
+    fn from(opcode: Opcode) -> Self {
+        match opcode {
+            Opcode::Add => 1,
+            Opcode::Multiply => 2,
+            Opcode::Input => 3,
+            Opcode::Output => 4,
+            Opcode::JumpIfTrue => 5,
+            Opcode::JumpIfFalse => 6,
+            Opcode::LessThan => 7,
+            Opcode::Equals => 8,
+            Opcode::RelativeBaseOffset => 9,
+            Opcode::Halt => 99,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParameterMode {
     Position,
     Immediate,
     Relative,
@@ -85,12 +185,22 @@ impl TryFrom<Value> for ParameterMode {
             0 => ParameterMode::Position,
             1 => ParameterMode::Immediate,
             2 => ParameterMode::Relative,
-            _ => return Err(IntCodeError::InvalidParameterMode(value)),
+            _ => return Err(IntCodeErrorKind::InvalidParameterMode(value).into()),
         })
     }
 }
 
-#[derive(Debug, Clone)]
+impl From<ParameterMode> for Value {
+    fn from(mode: ParameterMode) -> Self {
+        match mode {
+            ParameterMode::Position => 0,
+            ParameterMode::Immediate => 1,
+            ParameterMode::Relative => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 struct Instruction {
     opcode: Opcode,
     parameters: (ParameterMode, ParameterMode, ParameterMode),
@@ -111,116 +221,482 @@ impl TryFrom<Value> for Instruction {
     }
 }
 
-#[derive(Debug)]
-pub struct Computer {
-    memory: Memory,
+/// State of a `Computer` after executing instructions with `step` or `run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status<W = Value> {
+    Running,
+    NeedsInput,
+    Output(W),
+    Halted,
+}
+
+/// An intcode computer, with its input and output devices. Values are `W`
+/// words, `Value` by default; see `Word` for the other types available.
+#[derive(Debug, Clone)]
+pub struct Computer<I = mpsc::Receiver<Value>, O = mpsc::Sender<Value>, W = Value> {
+    memory: Memory<W>,
     instruction_pointer: Address,
-    receiver: mpsc::Receiver<Value>,
-    sender: mpsc::Sender<Value>,
+    input: I,
+    output: O,
     relative_base: isize,
+    last_write: Option<(Address, W)>,
+    tracer: trace::Tracer<W>,
+    cache: cache::InstructionCache,
+    self_modification: self_modification::SelfModificationDetector<W>,
+    profiling: bool,
+    profile: Profile,
+    coverage: Option<Coverage>,
+    history: history::History<W>,
+    instruction_count: u64,
+    instruction_limit: Option<u64>,
+    deadline: Option<Instant>,
 }
 
 impl Computer {
     pub fn new() -> (Self, mpsc::Sender<Value>, mpsc::Receiver<Value>) {
         let (tx, receiver) = mpsc::channel();
         let (sender, rx) = mpsc::channel();
-        let computer = Computer {
-            memory: Memory::new(),
-            instruction_pointer: 0,
-            receiver,
-            sender,
-            relative_base: 0,
-        };
-        (computer, tx, rx)
+        (Computer::with_io(receiver, sender), tx, rx)
     }
 
     pub fn with_mpsc(receiver: mpsc::Receiver<Value>, sender: mpsc::Sender<Value>) -> Self {
+        Computer::with_io(receiver, sender)
+    }
+}
+
+impl<I: Input, O: Output> Computer<I, O> {
+    pub fn with_io(input: I, output: O) -> Self {
+        Computer::with_devices(input, output)
+    }
+}
+
+impl<I: Input<W>, O: Output<W>, W: Word> Computer<I, O, W> {
+    /// Same as `with_io`, for any type of word. The type usually has to be
+    /// given, as in `Computer::<_, _, i128>::with_devices(input, output)`.
+    pub fn with_devices(input: I, output: O) -> Self {
         Computer {
             memory: Memory::new(),
             instruction_pointer: 0,
-            receiver,
-            sender,
+            input,
+            output,
             relative_base: 0,
+            last_write: None,
+            tracer: trace::Tracer::default(),
+            cache: cache::InstructionCache::default(),
+            self_modification: self_modification::SelfModificationDetector::default(),
+            profiling: false,
+            profile: Profile::default(),
+            coverage: None,
+            history: history::History::default(),
+            instruction_count: 0,
+            instruction_limit: None,
+            deadline: None,
         }
     }
 
-    pub fn load_program(&mut self, program: Program) -> &mut Self {
-        self.memory = program;
+    pub fn input_device(&self) -> &I {
+        &self.input
+    }
+
+    pub fn input_device_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    pub fn output_device(&self) -> &O {
+        &self.output
+    }
+
+    pub fn output_device_mut(&mut self) -> &mut O {
+        &mut self.output
+    }
+
+    pub fn load_program(&mut self, program: Vec<W>) -> &mut Self {
+        self.memory.load(program);
+        self.cache.clear();
+        self.self_modification.clear();
+        self.profile.restart();
         self.reset();
         self
     }
 
-    pub fn memory(&self) -> &Memory {
+    pub fn memory(&self) -> &Memory<W> {
         &self.memory
     }
 
+    /// Fail with `IntCodeErrorKind::InvalidAddress` when the program accesses
+    /// an address above `max_address`, instead of allocating memory for it.
+    pub fn set_max_address(&mut self, max_address: Option<usize>) -> &mut Self {
+        self.memory.set_max_address(max_address);
+        self
+    }
+
+    /// Keep decoded instructions to execute them faster the next time, which
+    /// is the default. Writes to memory invalidate the instructions they
+    /// modify.
+    pub fn set_instruction_cache(&mut self, enabled: bool) -> &mut Self {
+        self.cache.set_enabled(enabled);
+        self
+    }
+
+    /// Record the writes of the program to addresses it executed before, see
+    /// `code_writes`. Off by default, as it slows execution down.
+    pub fn set_self_modification_detection(&mut self, enabled: bool) -> &mut Self {
+        self.self_modification.set_enabled(enabled);
+        self
+    }
+
+    /// Writes to executed addresses since the program was loaded, when
+    /// `set_self_modification_detection` is on. Writes with `write_memory`
+    /// are not included.
+    pub fn code_writes(&self) -> &[CodeWrite<W>] {
+        self.self_modification.writes()
+    }
+
+    /// Count the instructions executed, see `profile`. Off by default.
+    /// Changing the setting clears the profile.
+    pub fn set_profiling(&mut self, enabled: bool) -> &mut Self {
+        self.profiling = enabled;
+        self.profile = Profile::default();
+        self
+    }
+
+    /// Instructions executed while profiling. Loading a program again adds to
+    /// the same profile, so it can cover several runs.
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Record the addresses executed, see `coverage`. Off by default.
+    pub fn set_coverage(&mut self, enabled: bool) -> &mut Self {
+        self.coverage = if enabled {
+            Some(Coverage::default())
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Addresses executed since coverage was turned on, by every program
+    /// loaded. Coverage of other computers can be merged into it.
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Record every instruction executed and what it changed, to rewind the
+    /// computer with `rewind_to`. Off by default, as the history grows with
+    /// every instruction.
+    pub fn set_history(&mut self, enabled: bool) -> &mut Self {
+        self.history.set_enabled(enabled);
+        self
+    }
+
+    /// Instructions executed since the history was turned on or the program
+    /// was loaded, oldest first.
+    pub fn history(&self) -> &[HistoryEntry<W>] {
+        self.history.entries()
+    }
+
+    /// The last instruction in the history that wrote to `address`.
+    pub fn last_writer(&self, address: Address) -> Option<&HistoryEntry<W>> {
+        self.history
+            .entries()
+            .iter()
+            .rev()
+            .find(|entry| matches!(&entry.write, Some(write) if write.address == address))
+    }
+
+    /// Undo instructions until `instruction_count` of them have been executed.
+    /// The input they read is read again before the input device, but their
+    /// output is not taken back, and neither are writes with `write_memory`.
+    pub fn rewind_to(&mut self, instruction_count: u64) -> IntCodeResult<()> {
+        let recorded = self
+            .history
+            .start()
+            .is_some_and(|start| start <= instruction_count);
+        if instruction_count > self.instruction_count
+            || (instruction_count < self.instruction_count && !recorded)
+        {
+            return Err(IntCodeErrorKind::NotInHistory(instruction_count).into());
+        }
+
+        while self.instruction_count > instruction_count {
+            let entry = self
+                .history
+                .undo()
+                .expect("instruction missing from history");
+            if let Some(write) = entry.write {
+                self.memory.write(write.address, write.old)?;
+                self.cache.invalidate(write.address);
+            }
+
+            self.instruction_pointer = entry.instruction_pointer;
+            self.relative_base = entry.relative_base;
+            self.instruction_count = entry.instruction_count;
+        }
+
+        self.last_write = None;
+        Ok(())
+    }
+
+    /// Undo the last instruction executed, see `rewind_to`.
+    pub fn step_back(&mut self) -> IntCodeResult<()> {
+        self.rewind_to(self.instruction_count.saturating_sub(1))
+    }
+
+    /// Write directly to memory, growing it if needed.
+    pub fn write_memory(&mut self, address: Address, value: W) -> IntCodeResult<()> {
+        self.write_address(address, value)
+    }
+
+    pub fn instruction_pointer(&self) -> Address {
+        self.instruction_pointer
+    }
+
+    pub fn set_instruction_pointer(&mut self, address: Address) -> &mut Self {
+        self.instruction_pointer = address;
+        self
+    }
+
+    pub fn relative_base(&self) -> isize {
+        self.relative_base
+    }
+
+    /// Address and value written by the last instruction, if it wrote to
+    /// memory.
+    pub fn last_write(&self) -> Option<(Address, W)> {
+        self.last_write.clone()
+    }
+
+    /// Run the program until it halts, waiting on the input device whenever a
+    /// value is needed and writing every output to the output device.
     pub fn execute(&mut self) -> IntCodeResult<()> {
-        let mut result = Ok(());
-        while let Ok(_) = result {
-            let Instruction { parameters, opcode } = self.read_instruction()?;
-            result = match opcode {
-                Opcode::Add => self.add(parameters),
-                Opcode::Multiply => self.multiply(parameters),
-                Opcode::Input => self.input(parameters),
-                Opcode::Output => self.output(parameters),
-                Opcode::JumpIfTrue => self.jump_if_true(parameters),
-                Opcode::JumpIfFalse => self.jump_if_false(parameters),
-                Opcode::LessThan => self.less_than(parameters),
-                Opcode::Equals => self.equals(parameters),
-                Opcode::RelativeBaseOffset => self.relative_base_offset(parameters),
-                Opcode::Halt => break,
-            };
+        loop {
+            let instruction_pointer = self.instruction_pointer;
+            match self.execute_instruction(true)? {
+                Status::Output(value) => self.write_output(value, instruction_pointer)?,
+                Status::Halted => return Ok(()),
+                Status::Running | Status::NeedsInput => (),
+            }
         }
+    }
 
-        result
+    /// Execute a single instruction. Never blocks: if no input is available,
+    /// the instruction pointer is left on the `Input` instruction and
+    /// `Status::NeedsInput` is returned. Outputs are returned to the caller
+    /// instead of being written to the output device.
+    pub fn step(&mut self) -> IntCodeResult<Status<W>> {
+        self.execute_instruction(false)
+    }
+
+    /// Execute instructions until the program halts, needs an input or
+    /// produces an output.
+    pub fn run(&mut self) -> IntCodeResult<Status<W>> {
+        loop {
+            match self.step()? {
+                Status::Running => (),
+                status => return Ok(status),
+            }
+        }
     }
 
     pub fn reset(&mut self) -> &mut Self {
         self.instruction_pointer = 0;
+        self.instruction_count = 0;
+        self.history.clear();
+        self
+    }
+
+    /// Number of instructions executed since the program was loaded.
+    pub fn instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+
+    /// Fail with `IntCodeErrorKind::InstructionLimit` once this many
+    /// instructions have been executed since the program was loaded.
+    pub fn set_instruction_limit(&mut self, limit: Option<u64>) -> &mut Self {
+        self.instruction_limit = limit;
         self
     }
 
-    fn read_address(&mut self, address: Address) -> IntCodeResult<Value> {
-        if address.is_negative() {
-            return Err(IntCodeError::InvalidAddress(address));
+    /// Fail with `IntCodeErrorKind::DeadlineExceeded` once this deadline has
+    /// passed. It is checked between instructions, not while waiting for input,
+    /// and only once every 1024 instructions, so a few more may run after it.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) -> &mut Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Same as `set_deadline`, from now.
+    pub fn set_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.set_deadline(Some(Instant::now() + timeout))
+    }
+
+    fn check_limits(&self) -> IntCodeResult<()> {
+        if let Some(limit) = self.instruction_limit {
+            if self.instruction_count >= limit {
+                return Err(IntCodeErrorKind::InstructionLimit(limit).into());
+            }
+        }
+
+        if let Some(deadline) = self.deadline {
+            if self
+                .instruction_count
+                .is_multiple_of(DEADLINE_CHECK_INTERVAL)
+                && Instant::now() >= deadline
+            {
+                return Err(IntCodeErrorKind::DeadlineExceeded.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write to the output device, for the instruction at `instruction_pointer`.
+    fn write_output(&mut self, value: W, instruction_pointer: Address) -> IntCodeResult<()> {
+        self.output.write(value).map_err(|error| {
+            error.with_context(ErrorContext::new(
+                &self.memory,
+                instruction_pointer,
+                self.relative_base,
+                self.instruction_count,
+            ))
+        })
+    }
+
+    fn execute_instruction(&mut self, blocking: bool) -> IntCodeResult<Status<W>> {
+        let instruction_pointer = self.instruction_pointer;
+        let relative_base = self.relative_base;
+
+        let result = self.check_limits().and_then(|_| {
+            if self.tracer.0.is_none() {
+                self.execute_untraced(blocking)
+            } else {
+                let mut sink = self.tracer.0.take().unwrap();
+                let result = self.execute_traced(blocking, sink.as_mut());
+                self.tracer.0 = Some(sink);
+                result
+            }
+        });
+
+        match result {
+            Ok(Status::NeedsInput) | Ok(Status::Halted) => (),
+            Ok(_) => {
+                self.history.commit(self.instruction_count);
+                self.instruction_count += 1;
+            }
+            Err(error) => {
+                return Err(error.with_context(ErrorContext::new(
+                    &self.memory,
+                    instruction_pointer,
+                    relative_base,
+                    self.instruction_count,
+                )))
+            }
+        }
+
+        result
+    }
+
+    fn execute_untraced(&mut self, blocking: bool) -> IntCodeResult<Status<W>> {
+        let start = self.instruction_pointer;
+        self.last_write = None;
+        self.history.begin(start, self.relative_base);
+        let Instruction { parameters, opcode } = self.read_instruction()?;
+        self.self_modification
+            .execute(start, opcode.parameter_count() + 1);
+        let status = match opcode {
+            Opcode::Add => self.add(parameters)?,
+            Opcode::Multiply => self.multiply(parameters)?,
+            Opcode::Input => self.input(parameters, blocking)?,
+            Opcode::Output => self.output(parameters)?,
+            Opcode::JumpIfTrue => self.jump_if_true(parameters)?,
+            Opcode::JumpIfFalse => self.jump_if_false(parameters)?,
+            Opcode::LessThan => self.less_than(parameters)?,
+            Opcode::Equals => self.equals(parameters)?,
+            Opcode::RelativeBaseOffset => self.relative_base_offset(parameters)?,
+            Opcode::Halt => Status::Halted,
+        };
+
+        // Stay on blocking instructions, so they are executed again next time.
+        if let Status::NeedsInput | Status::Halted = status {
+            self.instruction_pointer = start;
+        }
+
+        if self.profiling {
+            self.record_profile(start, opcode, parameters, &status);
         }
 
-        let address = address as usize;
-        if address >= self.memory.len() {
-            self.expand_memory(address + 1);
+        if let Some(coverage) = &mut self.coverage {
+            if status != Status::NeedsInput {
+                coverage.record(start);
+            }
         }
 
-        Ok(self.memory[address as usize])
+        Ok(status)
     }
 
-    fn write_address(&mut self, address: Address, value: Value) -> IntCodeResult<()> {
-        if address.is_negative() {
-            return Err(IntCodeError::InvalidAddress(address));
+    fn record_profile(
+        &mut self,
+        start: Address,
+        opcode: Opcode,
+        parameters: Parameters,
+        status: &Status<W>,
+    ) {
+        if let Status::NeedsInput | Status::Halted = status {
+            return;
         }
 
-        let address = address as usize;
-        if address >= self.memory.len() {
-            self.expand_memory(address + 1);
+        self.profile.record(start, opcode);
+        if let Opcode::JumpIfTrue | Opcode::JumpIfFalse = opcode {
+            let next = start + 3;
+            let target = self.instruction_pointer;
+            let taken = Some(target).filter(|&target| target != next);
+            self.profile.record_branch(start, taken);
+            if taken.is_none() {
+                return;
+            }
+
+            if parameters.1 == ParameterMode::Relative {
+                self.profile.return_to(target);
+            } else if self.relative_base >= 0
+                && self.memory.get(self.relative_base as usize) == W::from_value(next)
+            {
+                self.profile.call(target, next);
+            }
         }
+    }
 
-        self.memory[address as usize] = value;
-        Ok(())
+    /// Whether something observes every instruction, so that it has to be
+    /// interpreted.
+    fn instrumented(&self) -> bool {
+        self.tracer.0.is_some()
+            || self.self_modification.is_enabled()
+            || self.profiling
+            || self.coverage.is_some()
+            || self.history.is_enabled()
     }
 
-    fn expand_memory(&mut self, size: usize) {
-        self.memory.resize_with(size, Default::default)
+    fn read_address(&mut self, address: Address) -> IntCodeResult<W> {
+        self.memory.read(address)
     }
 
-    fn read_next(&mut self, mode: ParameterMode) -> IntCodeResult<Value> {
+    fn write_address(&mut self, address: Address, value: W) -> IntCodeResult<()> {
+        self.memory.write(address, value.clone())?;
+        self.cache.invalidate(address);
+        self.last_write = Some((address, value));
+        Ok(())
+    }
+
+    fn read_next(&mut self, mode: ParameterMode) -> IntCodeResult<W> {
         let result = match mode {
             ParameterMode::Position => {
-                let address = self.read_address(self.instruction_pointer)?;
+                let address = self.read_next_address()?;
                 self.read_address(address)
             }
             ParameterMode::Immediate => self.read_address(self.instruction_pointer),
             ParameterMode::Relative => {
-                let address = self.read_address(self.instruction_pointer)?;
-                self.read_address(address + self.relative_base)
+                let address = self.read_next_address()?;
+                self.read_address(self.relative_address(address)?)
             }
         };
 
@@ -228,81 +704,146 @@ impl Computer {
         result
     }
 
-    fn write_next(&mut self, value: Value, mode: ParameterMode) -> IntCodeResult<()> {
-        match mode {
-            ParameterMode::Position => self
-                .read_next(ParameterMode::Immediate)
-                .and_then(|address| self.write_address(address, value)),
-            ParameterMode::Immediate => Err(IntCodeError::WriteImmediateMode),
-            ParameterMode::Relative => self
-                .read_next(ParameterMode::Immediate)
-                .and_then(|address| self.write_address(address + self.relative_base, value)),
+    fn write_next(&mut self, value: W, mode: ParameterMode) -> IntCodeResult<()> {
+        let address = match mode {
+            ParameterMode::Position => self.read_next_address()?,
+            ParameterMode::Immediate => return Err(IntCodeErrorKind::WriteImmediateMode.into()),
+            ParameterMode::Relative => {
+                let address = self.read_next_address()?;
+                self.relative_address(address)?
+            }
+        };
+
+        self.instruction_pointer += 1;
+        self.self_modification
+            .write(address, &value, self.instruction_count);
+        if self.history.is_enabled() {
+            self.history.write(MemoryWrite {
+                address,
+                old: self.memory.get(address as usize),
+                new: value.clone(),
+            });
         }
+
+        self.write_address(address, value)
+    }
+
+    /// Read the address held by the parameter at the instruction pointer,
+    /// without moving it.
+    fn read_next_address(&mut self) -> IntCodeResult<Address> {
+        let word = self.read_address(self.instruction_pointer)?;
+        to_address(&word)
+    }
+
+    fn relative_address(&self, offset: Address) -> IntCodeResult<Address> {
+        offset
+            .checked_add(self.relative_base)
+            .ok_or_else(|| IntCodeErrorKind::Overflow.into())
     }
 
     fn read_instruction(&mut self) -> IntCodeResult<Instruction> {
-        self.read_next(ParameterMode::Immediate)
-            .and_then(Instruction::try_from)
+        let address = self.instruction_pointer;
+        if let Some(instruction) = self.cache.get(address) {
+            self.instruction_pointer += 1;
+            return Ok(instruction);
+        }
+
+        let word = self.read_next(ParameterMode::Immediate)?;
+        let value = word
+            .to_value()
+            .ok_or(IntCodeErrorKind::InvalidOpCode(word.saturating_value()))?;
+        let instruction = Instruction::try_from(value)?;
+        self.cache.insert(address, instruction);
+        Ok(instruction)
     }
 
-    fn add(&mut self, parameters: Parameters) -> IntCodeResult<()> {
+    fn add(&mut self, parameters: Parameters) -> IntCodeResult<Status<W>> {
         let a = self.read_next(parameters.0)?;
         let b = self.read_next(parameters.1)?;
-        self.write_next(a + b, parameters.2)
+        let sum = a.checked_add(&b).ok_or(IntCodeErrorKind::Overflow)?;
+        self.write_next(sum, parameters.2)?;
+        Ok(Status::Running)
     }
 
-    fn multiply(&mut self, parameters: Parameters) -> IntCodeResult<()> {
+    fn multiply(&mut self, parameters: Parameters) -> IntCodeResult<Status<W>> {
         let a = self.read_next(parameters.0)?;
         let b = self.read_next(parameters.1)?;
-        self.write_next(a * b, parameters.2)
+        let product = a.checked_mul(&b).ok_or(IntCodeErrorKind::Overflow)?;
+        self.write_next(product, parameters.2)?;
+        Ok(Status::Running)
     }
 
-    fn input(&mut self, parameters: Parameters) -> IntCodeResult<()> {
-        let value = self.receiver.recv().or(Err(IntCodeError::ReadError))?;
-        self.write_next(value, parameters.0)
+    fn input(&mut self, parameters: Parameters, blocking: bool) -> IntCodeResult<Status<W>> {
+        let value = if let Some(value) = self.history.replay() {
+            value
+        } else if blocking {
+            self.input.read()?
+        } else {
+            match self.input.try_read()? {
+                Some(value) => value,
+                None => return Ok(Status::NeedsInput),
+            }
+        };
+
+        if self.history.is_enabled() {
+            self.history.input(value.clone());
+        }
+
+        self.write_next(value, parameters.0)?;
+        Ok(Status::Running)
     }
 
-    fn output(&mut self, parameters: Parameters) -> IntCodeResult<()> {
-        let value = self.read_next(parameters.0)?;
-        self.sender.send(value).or(Err(IntCodeError::WriteError))
+    fn output(&mut self, parameters: Parameters) -> IntCodeResult<Status<W>> {
+        self.read_next(parameters.0).map(Status::Output)
     }
 
-    fn jump_if_true(&mut self, parameters: Parameters) -> IntCodeResult<()> {
+    fn jump_if_true(&mut self, parameters: Parameters) -> IntCodeResult<Status<W>> {
         let a = self.read_next(parameters.0)?;
         let b = self.read_next(parameters.1)?;
-        if a != 0 {
-            self.instruction_pointer = b;
+        if !a.is_zero() {
+            self.instruction_pointer = to_address(&b)?;
         }
 
-        Ok(())
+        Ok(Status::Running)
     }
 
-    fn jump_if_false(&mut self, parameters: Parameters) -> IntCodeResult<()> {
+    fn jump_if_false(&mut self, parameters: Parameters) -> IntCodeResult<Status<W>> {
         let a = self.read_next(parameters.0)?;
         let b = self.read_next(parameters.1)?;
-        if a == 0 {
-            self.instruction_pointer = b;
+        if a.is_zero() {
+            self.instruction_pointer = to_address(&b)?;
         }
 
-        Ok(())
+        Ok(Status::Running)
     }
 
-    fn less_than(&mut self, parameters: Parameters) -> IntCodeResult<()> {
+    fn less_than(&mut self, parameters: Parameters) -> IntCodeResult<Status<W>> {
         let a = self.read_next(parameters.0)?;
         let b = self.read_next(parameters.1)?;
 
-        self.write_next(if a < b { 1 } else { 0 }, parameters.2)
+        self.write_next(W::from_value(if a < b { 1 } else { 0 }), parameters.2)?;
+        Ok(Status::Running)
     }
 
-    fn equals(&mut self, parameters: Parameters) -> IntCodeResult<()> {
+    fn equals(&mut self, parameters: Parameters) -> IntCodeResult<Status<W>> {
         let a = self.read_next(parameters.0)?;
         let b = self.read_next(parameters.1)?;
 
-        self.write_next(if a == b { 1 } else { 0 }, parameters.2)
+        self.write_next(W::from_value(if a == b { 1 } else { 0 }), parameters.2)?;
+        Ok(Status::Running)
     }
 
-    fn relative_base_offset(&mut self, parameters: Parameters) -> IntCodeResult<()> {
-        self.relative_base += self.read_next(parameters.0)?;
-        Ok(())
+    fn relative_base_offset(&mut self, parameters: Parameters) -> IntCodeResult<Status<W>> {
+        let offset = self.read_next(parameters.0)?;
+        self.relative_base = offset
+            .to_value()
+            .and_then(|offset| self.relative_base.checked_add(offset))
+            .ok_or(IntCodeErrorKind::Overflow)?;
+        Ok(Status::Running)
     }
 }
+
+fn to_address<W: Word>(word: &W) -> IntCodeResult<Address> {
+    word.to_value()
+        .ok_or_else(|| IntCodeErrorKind::InvalidAddress(word.saturating_value()).into())
+}
@@ -0,0 +1,113 @@
+use super::{IntCodeResult, Program, Scheduler, Value};
+use permutohedron::Heap;
+use std::cmp::Reverse;
+use std::thread;
+
+/// Copies of a program connected in series, each one reading its phase
+/// setting then the outputs of the previous one. In a feedback loop, the
+/// outputs of the last one also go back to the first one.
+#[derive(Debug, Clone)]
+pub struct Amplifiers {
+    program: Program,
+    feedback: bool,
+}
+
+impl Amplifiers {
+    pub fn chain(program: Program) -> Self {
+        Amplifiers {
+            program,
+            feedback: false,
+        }
+    }
+
+    pub fn feedback_loop(program: Program) -> Self {
+        Amplifiers {
+            program,
+            feedback: true,
+        }
+    }
+
+    /// Run one amplifier per phase setting, the first one also reading
+    /// `input`, until they all halt or wait for input. Returns the last output
+    /// of the last amplifier, if any.
+    pub fn run(&self, phases: &[Value], input: Value) -> IntCodeResult<Option<Value>> {
+        let mut scheduler = Scheduler::new();
+        for &phase in phases {
+            let id = scheduler.add(self.program.clone());
+            scheduler.send(id, phase);
+        }
+
+        for id in 1..phases.len() {
+            scheduler.connect(id - 1, id);
+        }
+
+        let last = match phases.len() {
+            0 => return Ok(None),
+            len => len - 1,
+        };
+        if self.feedback {
+            scheduler.connect(last, 0);
+        }
+
+        scheduler.send(0, input).run()?;
+
+        // In a loop, the last signal is left unread by the first amplifier.
+        Ok(if self.feedback {
+            scheduler.machine(0).input_device().back().copied()
+        } else {
+            scheduler.machine(last).output_device().last().copied()
+        })
+    }
+
+    /// Try every permutation of the phase settings with an input of 0,
+    /// returning the one giving the highest signal, with the signal. Ties go
+    /// to the first permutation in lexicographic order.
+    pub fn best_phases(&self, settings: &[Value]) -> IntCodeResult<Option<(Vec<Value>, Value)>> {
+        self.best_of(permutations(settings))
+    }
+
+    /// Same as `best_phases`, trying the permutations on all the available
+    /// cores.
+    pub fn best_phases_parallel(
+        &self,
+        settings: &[Value],
+    ) -> IntCodeResult<Option<(Vec<Value>, Value)>> {
+        let permutations = permutations(settings);
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        let chunk_size = permutations.len().div_ceil(threads).max(1);
+
+        let results = thread::scope(|scope| {
+            let handles: Vec<_> = permutations
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || self.best_of(chunk.to_vec())))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("amplifier thread panicked"))
+                .collect::<IntCodeResult<Vec<_>>>()
+        })?;
+
+        Ok(results.into_iter().flatten().max_by_key(ranking))
+    }
+
+    fn best_of(&self, permutations: Vec<Vec<Value>>) -> IntCodeResult<Option<(Vec<Value>, Value)>> {
+        let mut results = Vec::new();
+        for phases in permutations {
+            if let Some(signal) = self.run(&phases, 0)? {
+                results.push((phases, signal));
+            }
+        }
+
+        Ok(results.into_iter().max_by_key(ranking))
+    }
+}
+
+fn permutations(settings: &[Value]) -> Vec<Vec<Value>> {
+    let mut settings = settings.to_vec();
+    Heap::new(&mut settings).collect()
+}
+
+/// Highest signal first, then the first phases in lexicographic order.
+fn ranking((phases, signal): &(Vec<Value>, Value)) -> (Value, Reverse<Vec<Value>>) {
+    (*signal, Reverse(phases.clone()))
+}
@@ -0,0 +1,89 @@
+use super::{Computer, IntCodeResult, Program, Status, Value};
+use std::collections::VecDeque;
+
+/// A computer running an ASCII program, exchanging lines of text. Outputs
+/// outside of the ASCII range are results, see `results`.
+#[derive(Debug, Clone)]
+pub struct AsciiComputer {
+    computer: Computer<VecDeque<Value>, Vec<Value>>,
+    results: Vec<Value>,
+    halted: bool,
+}
+
+impl AsciiComputer {
+    pub fn new(program: Program) -> Self {
+        let mut computer = Computer::with_io(VecDeque::new(), Vec::new());
+        computer.load_program(program);
+        AsciiComputer {
+            computer,
+            results: Vec::new(),
+            halted: false,
+        }
+    }
+
+    pub fn computer(&self) -> &Computer<VecDeque<Value>, Vec<Value>> {
+        &self.computer
+    }
+
+    pub fn computer_mut(&mut self) -> &mut Computer<VecDeque<Value>, Vec<Value>> {
+        &mut self.computer
+    }
+
+    /// Queue a line of input, the newline is added.
+    pub fn send_line(&mut self, line: &str) {
+        let input = self.computer.input_device_mut();
+        input.extend(line.bytes().map(Value::from));
+        input.push_back(Value::from(b'\n'));
+    }
+
+    /// Next line of output, without the newline. A line the program did not
+    /// finish before waiting for input or halting is returned as is, and
+    /// `None` when there is no output left.
+    pub fn read_line(&mut self) -> IntCodeResult<Option<String>> {
+        let mut line = String::new();
+        loop {
+            match self.next()? {
+                Some(b'\n') => return Ok(Some(line)),
+                Some(byte) => line.push(char::from(byte)),
+                None if line.is_empty() => return Ok(None),
+                None => return Ok(Some(line)),
+            }
+        }
+    }
+
+    /// All the output until the program waits for input or halts.
+    pub fn read_until_prompt(&mut self) -> IntCodeResult<String> {
+        let mut text = String::new();
+        while let Some(byte) = self.next()? {
+            text.push(char::from(byte));
+        }
+
+        Ok(text)
+    }
+
+    /// Outputs outside of the ASCII range so far, usually the answer.
+    pub fn results(&self) -> &[Value] {
+        &self.results
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Next ASCII output, keeping the others as results, or `None` when the
+    /// program waits for input or halts.
+    fn next(&mut self) -> IntCodeResult<Option<u8>> {
+        loop {
+            match self.computer.run()? {
+                Status::Output(value @ 0..=127) => return Ok(Some(value as u8)),
+                Status::Output(value) => self.results.push(value),
+                Status::NeedsInput => return Ok(None),
+                Status::Halted => {
+                    self.halted = true;
+                    return Ok(None);
+                }
+                Status::Running => unreachable!(),
+            }
+        }
+    }
+}
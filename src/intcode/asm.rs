@@ -0,0 +1,370 @@
+//! Assembler for the syntax produced by `disasm`.
+//!
+//! Each line holds an optional label (`loop:`) or address (`12:`, checked
+//! against the actual address), followed by an instruction, a `data`
+//! directive or a macro. Anything after a `;` is a comment.
+//!
+//! Operands are written `[pos]`, `#imm` or `rb+off`, where `pos`, `imm` and
+//! `off` are sums of numbers and labels (`[counter+1]`, `#end-start`).
+//!
+//! Macros use a stack at the relative base, with `rb+0` as its top. Programs
+//! using them should first point the relative base to free memory (for
+//! example, `arb #stack` with a `stack:` label at the end of the program).
+//!
+//! - `call target`: push the return address, then jump to `target`.
+//! - `ret`: pop the return address and jump to it.
+//! - `jmp target`: jump to `target`.
+
+use super::{Opcode, ParameterMode, Program, Value};
+use std::collections::HashMap;
+use std::fmt;
+
+const CALL_WIDTH: usize = 9;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmErrorKind {
+    UnknownMnemonic(String),
+    OperandCount { expected: usize, found: usize },
+    InvalidOperand(String),
+    InvalidLabel(String),
+    DuplicateLabel(String),
+    UndefinedLabel(String),
+    AddressMismatch { expected: usize, found: usize },
+    WriteImmediateMode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub kind: AsmErrorKind,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            AsmErrorKind::UnknownMnemonic(mnemonic) => write!(f, "unknown mnemonic `{}`", mnemonic),
+            AsmErrorKind::OperandCount { expected, found } => {
+                write!(f, "expected {} operands, found {}", expected, found)
+            }
+            AsmErrorKind::InvalidOperand(operand) => write!(f, "invalid operand `{}`", operand),
+            AsmErrorKind::InvalidLabel(label) => write!(f, "invalid label `{}`", label),
+            AsmErrorKind::DuplicateLabel(label) => write!(f, "duplicate label `{}`", label),
+            AsmErrorKind::UndefinedLabel(label) => write!(f, "undefined label `{}`", label),
+            AsmErrorKind::AddressMismatch { expected, found } => write!(
+                f,
+                "address {} was given, but the line is at address {}",
+                expected, found
+            ),
+            AsmErrorKind::WriteImmediateMode => write!(f, "cannot write to an immediate operand"),
+        }
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+type AsmResult<T> = std::result::Result<T, AsmError>;
+
+#[derive(Debug, Clone)]
+enum Term {
+    Number(Value),
+    Label(String),
+}
+
+/// Sum of signed terms.
+#[derive(Debug, Clone)]
+struct Expr(Vec<(bool, Term)>);
+
+impl Expr {
+    fn number(value: Value) -> Self {
+        Expr(vec![(false, Term::Number(value))])
+    }
+
+    fn evaluate(&self, labels: &HashMap<String, usize>) -> Result<Value, AsmErrorKind> {
+        self.0.iter().try_fold(0, |acc, (negative, term)| {
+            let value = match term {
+                Term::Number(value) => *value,
+                Term::Label(label) => *labels
+                    .get(label)
+                    .ok_or_else(|| AsmErrorKind::UndefinedLabel(label.clone()))?
+                    as Value,
+            };
+            Ok(if *negative { acc - value } else { acc + value })
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Operand {
+    mode: ParameterMode,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    Instruction(Opcode, Vec<Operand>),
+    Data(Vec<Expr>),
+}
+
+impl Item {
+    fn width(&self) -> usize {
+        match self {
+            Item::Instruction(_, operands) => 1 + operands.len(),
+            Item::Data(values) => values.len(),
+        }
+    }
+}
+
+/// Assemble a program.
+pub fn assemble(source: &str) -> AsmResult<Program> {
+    let mut labels = HashMap::new();
+    let mut items = Vec::new();
+    let mut address = 0;
+
+    for (idx, line) in source.lines().enumerate() {
+        let line_number = idx + 1;
+        let error = |kind| AsmError {
+            line: line_number,
+            kind,
+        };
+
+        let mut text = line.split(';').next().unwrap_or_default().trim();
+
+        // Labels and addresses.
+        while let Some(colon) = text.find(':') {
+            let label = text[..colon].trim();
+            if let Ok(expected) = label.parse::<usize>() {
+                if expected != address {
+                    return Err(error(AsmErrorKind::AddressMismatch {
+                        expected,
+                        found: address,
+                    }));
+                }
+            } else if is_identifier(label) {
+                if labels.insert(label.to_string(), address).is_some() {
+                    return Err(error(AsmErrorKind::DuplicateLabel(label.to_string())));
+                }
+            } else {
+                return Err(error(AsmErrorKind::InvalidLabel(label.to_string())));
+            }
+
+            text = text[colon + 1..].trim();
+        }
+
+        if text.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, rest) = match text.find(char::is_whitespace) {
+            Some(idx) => (&text[..idx], text[idx..].trim()),
+            None => (text, ""),
+        };
+        let arguments: Vec<&str> = if rest.is_empty() {
+            Vec::new()
+        } else {
+            rest.split(',').map(str::trim).collect()
+        };
+
+        for item in parse_item(mnemonic, &arguments, address).map_err(error)? {
+            address += item.width();
+            items.push((line_number, item));
+        }
+    }
+
+    let mut program = Program::with_capacity(address);
+    for (line, item) in items {
+        emit(&item, &labels, &mut program).map_err(|kind| AsmError { line, kind })?;
+    }
+
+    Ok(program)
+}
+
+fn parse_item(
+    mnemonic: &str,
+    arguments: &[&str],
+    address: usize,
+) -> Result<Vec<Item>, AsmErrorKind> {
+    let expect = |expected: usize| {
+        if arguments.len() == expected {
+            Ok(())
+        } else {
+            Err(AsmErrorKind::OperandCount {
+                expected,
+                found: arguments.len(),
+            })
+        }
+    };
+
+    let immediate = |expr| Operand {
+        mode: ParameterMode::Immediate,
+        expr,
+    };
+    let relative = |offset| Operand {
+        mode: ParameterMode::Relative,
+        expr: Expr::number(offset),
+    };
+
+    Ok(match mnemonic {
+        "data" => vec![Item::Data(
+            arguments
+                .iter()
+                .map(|argument| parse_expr(argument))
+                .collect::<Result<_, _>>()?,
+        )],
+        "call" => {
+            expect(1)?;
+            let target = parse_expr(arguments[0])?;
+            let return_address = Expr::number((address + CALL_WIDTH) as Value);
+            vec![
+                Item::Instruction(Opcode::RelativeBaseOffset, vec![immediate(Expr::number(1))]),
+                Item::Instruction(
+                    Opcode::Add,
+                    vec![
+                        immediate(return_address),
+                        immediate(Expr::number(0)),
+                        relative(0),
+                    ],
+                ),
+                Item::Instruction(
+                    Opcode::JumpIfTrue,
+                    vec![immediate(Expr::number(1)), immediate(target)],
+                ),
+            ]
+        }
+        "ret" => {
+            expect(0)?;
+            vec![
+                Item::Instruction(
+                    Opcode::RelativeBaseOffset,
+                    vec![immediate(Expr::number(-1))],
+                ),
+                Item::Instruction(
+                    Opcode::JumpIfFalse,
+                    vec![immediate(Expr::number(0)), relative(1)],
+                ),
+            ]
+        }
+        "jmp" => {
+            expect(1)?;
+            let target = parse_expr(arguments[0])?;
+            vec![Item::Instruction(
+                Opcode::JumpIfTrue,
+                vec![immediate(Expr::number(1)), immediate(target)],
+            )]
+        }
+        _ => {
+            let opcode = Opcode::from_mnemonic(mnemonic)
+                .ok_or_else(|| AsmErrorKind::UnknownMnemonic(mnemonic.to_string()))?;
+            expect(opcode.parameter_count())?;
+            let operands: Vec<Operand> = arguments
+                .iter()
+                .map(|argument| parse_operand(argument))
+                .collect::<Result<_, _>>()?;
+
+            if opcode.writes_memory()
+                && operands.last().map(|operand| operand.mode) == Some(ParameterMode::Immediate)
+            {
+                return Err(AsmErrorKind::WriteImmediateMode);
+            }
+
+            vec![Item::Instruction(opcode, operands)]
+        }
+    })
+}
+
+fn parse_operand(text: &str) -> Result<Operand, AsmErrorKind> {
+    let invalid = || AsmErrorKind::InvalidOperand(text.to_string());
+
+    let (mode, expr) = if let Some(rest) = text.strip_prefix('#') {
+        (ParameterMode::Immediate, rest)
+    } else if let Some(rest) = text.strip_prefix('[') {
+        (
+            ParameterMode::Position,
+            rest.strip_suffix(']').ok_or_else(invalid)?,
+        )
+    } else if let Some(rest) = text.strip_prefix("rb") {
+        (
+            ParameterMode::Relative,
+            if rest.is_empty() { "0" } else { rest },
+        )
+    } else {
+        return Err(invalid());
+    };
+
+    Ok(Operand {
+        mode,
+        expr: parse_expr(expr).map_err(|_| invalid())?,
+    })
+}
+
+fn parse_expr(text: &str) -> Result<Expr, AsmErrorKind> {
+    let invalid = || AsmErrorKind::InvalidOperand(text.to_string());
+    let mut terms = Vec::new();
+    let mut rest = text.trim();
+    let mut negative = false;
+
+    // A leading sign is allowed.
+    if let Some(stripped) = rest.strip_prefix('-') {
+        negative = true;
+        rest = stripped.trim_start();
+    } else if let Some(stripped) = rest.strip_prefix('+') {
+        rest = stripped.trim_start();
+    }
+
+    loop {
+        let end = rest.find(['+', '-']).unwrap_or(rest.len());
+        let token = rest[..end].trim();
+        let term = if let Ok(value) = token.parse::<Value>() {
+            Term::Number(value)
+        } else if is_identifier(token) {
+            Term::Label(token.to_string())
+        } else {
+            return Err(invalid());
+        };
+        terms.push((negative, term));
+
+        if end == rest.len() {
+            break;
+        }
+
+        negative = rest[end..].starts_with('-');
+        rest = rest[end + 1..].trim_start();
+    }
+
+    Ok(Expr(terms))
+}
+
+fn emit(
+    item: &Item,
+    labels: &HashMap<String, usize>,
+    program: &mut Program,
+) -> Result<(), AsmErrorKind> {
+    match item {
+        Item::Instruction(opcode, operands) => {
+            let modes = operands
+                .iter()
+                .rev()
+                .fold(0, |acc, operand| acc * 10 + Value::from(operand.mode));
+            program.push(modes * 100 + Value::from(*opcode));
+            for operand in operands {
+                program.push(operand.expr.evaluate(labels)?);
+            }
+        }
+        Item::Data(values) => {
+            for value in values {
+                program.push(value.evaluate(labels)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    text != "rb"
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
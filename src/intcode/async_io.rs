@@ -0,0 +1,218 @@
+use super::{
+    Computer, ErrorContext, Input, IntCodeErrorKind, IntCodeResult, Output, Status, Value, Word,
+};
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// Number of instructions executed by `execute_async` before giving the
+/// executor a chance to run other tasks.
+const YIELD_INTERVAL: u64 = 4096;
+
+/// An input device which can wait for values without blocking the thread.
+pub trait AsyncInput<W = Value>: Input<W> {
+    /// Ready once `try_read` has a value to return, or with an error if it
+    /// never will.
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<IntCodeResult<()>>;
+}
+
+impl<W> AsyncInput<W> for VecDeque<W> {
+    /// An empty queue is not refilled while the computer runs, so it fails.
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<IntCodeResult<()>> {
+        Poll::Ready(if self.is_empty() {
+            Err(IntCodeErrorKind::ReadError("input queue is empty").into())
+        } else {
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Shared<W> {
+    values: VecDeque<W>,
+    /// Task waiting for a value.
+    waker: Option<Waker>,
+    senders: usize,
+    receiver: bool,
+}
+
+/// Sending half of an `async_channel`. Sending never waits, as the channel
+/// is unbounded.
+#[derive(Debug)]
+pub struct AsyncSender<W = Value>(Arc<Mutex<Shared<W>>>);
+
+/// Receiving half of an `async_channel`, to use as the input device of a
+/// computer running with `execute_async`, or to await its outputs.
+#[derive(Debug)]
+pub struct AsyncReceiver<W = Value>(Arc<Mutex<Shared<W>>>);
+
+/// Unbounded queue working with any executor, as it only relies on the
+/// `Waker` of the receiving task.
+pub fn async_channel<W>() -> (AsyncSender<W>, AsyncReceiver<W>) {
+    let shared = Arc::new(Mutex::new(Shared {
+        values: VecDeque::new(),
+        waker: None,
+        senders: 1,
+        receiver: true,
+    }));
+    (AsyncSender(shared.clone()), AsyncReceiver(shared))
+}
+
+impl<W> AsyncSender<W> {
+    /// Queue a value, failing if the receiver was dropped.
+    pub fn send(&self, value: W) -> IntCodeResult<()> {
+        let mut shared = self.0.lock().unwrap();
+        if !shared.receiver {
+            return Err(IntCodeErrorKind::WriteError("output channel closed").into());
+        }
+
+        shared.values.push_back(value);
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+
+        Ok(())
+    }
+}
+
+impl<W> Clone for AsyncSender<W> {
+    fn clone(&self) -> Self {
+        self.0.lock().unwrap().senders += 1;
+        AsyncSender(self.0.clone())
+    }
+}
+
+impl<W> Drop for AsyncSender<W> {
+    fn drop(&mut self) {
+        let mut shared = self.0.lock().unwrap();
+        shared.senders -= 1;
+        if shared.senders == 0 {
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<W> AsyncReceiver<W> {
+    /// Next value, or `None` once the queue is empty and every sender was
+    /// dropped.
+    pub async fn recv(&mut self) -> Option<W> {
+        poll_fn(|cx| {
+            let mut shared = self.0.lock().unwrap();
+            match shared.values.pop_front() {
+                Some(value) => Poll::Ready(Some(value)),
+                None if shared.senders == 0 => Poll::Ready(None),
+                None => {
+                    shared.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    /// Values queued so far, without waiting.
+    pub fn try_iter(&mut self) -> impl Iterator<Item = W> + '_ {
+        std::iter::from_fn(move || self.0.lock().unwrap().values.pop_front())
+    }
+}
+
+impl<W> Drop for AsyncReceiver<W> {
+    fn drop(&mut self) {
+        self.0.lock().unwrap().receiver = false;
+    }
+}
+
+impl<W> Input<W> for AsyncReceiver<W> {
+    fn try_read(&mut self) -> IntCodeResult<Option<W>> {
+        let mut shared = self.0.lock().unwrap();
+        match shared.values.pop_front() {
+            Some(value) => Ok(Some(value)),
+            None if shared.senders == 0 => {
+                Err(IntCodeErrorKind::ReadError("input channel closed").into())
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// There is no way to block here without stalling the executor, so this
+    /// fails instead when no value is available. Use `execute_async`.
+    fn read(&mut self) -> IntCodeResult<W> {
+        self.try_read()?
+            .ok_or_else(|| IntCodeErrorKind::ReadError("no input available yet").into())
+    }
+}
+
+impl<W> AsyncInput<W> for AsyncReceiver<W> {
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<IntCodeResult<()>> {
+        let mut shared = self.0.lock().unwrap();
+        if !shared.values.is_empty() {
+            Poll::Ready(Ok(()))
+        } else if shared.senders == 0 {
+            Poll::Ready(Err(
+                IntCodeErrorKind::ReadError("input channel closed").into()
+            ))
+        } else {
+            shared.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl<W> Output<W> for AsyncSender<W> {
+    fn write(&mut self, value: W) -> IntCodeResult<()> {
+        self.send(value)
+    }
+}
+
+impl<I: AsyncInput<W>, O: Output<W>, W: Word> Computer<I, O, W> {
+    /// Same as `execute`, awaiting input instead of blocking the thread, so
+    /// that many computers can run on an executor. Long computations yield
+    /// regularly to the other tasks.
+    pub async fn execute_async(&mut self) -> IntCodeResult<()> {
+        loop {
+            let instruction_pointer = self.instruction_pointer;
+            match self.step()? {
+                Status::Running => {
+                    if self.instruction_count.is_multiple_of(YIELD_INTERVAL) {
+                        YieldNow(false).await;
+                    }
+                }
+                Status::Output(value) => self.write_output(value, instruction_pointer)?,
+                Status::NeedsInput => {
+                    poll_fn(|cx| self.input.poll_ready(cx))
+                        .await
+                        .map_err(|error| {
+                            error.with_context(ErrorContext::new(
+                                &self.memory,
+                                instruction_pointer,
+                                self.relative_base,
+                                self.instruction_count,
+                            ))
+                        })?
+                }
+                Status::Halted => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Pending once, letting the executor run other tasks first.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
@@ -0,0 +1,63 @@
+use super::{Address, Instruction};
+
+/// Width of the longest instruction, with its parameters.
+const MAX_WIDTH: usize = 4;
+/// Instructions are only cached below this address.
+const CACHE_LIMIT: usize = 1 << 16;
+
+/// Instructions already decoded, by address, to skip decoding them again.
+///
+/// Writing to memory invalidates the instructions that could include the
+/// address written to, so self-modifying programs see their changes.
+#[derive(Debug, Clone)]
+pub(super) struct InstructionCache {
+    enabled: bool,
+    entries: Vec<Option<Instruction>>,
+}
+
+impl Default for InstructionCache {
+    fn default() -> Self {
+        InstructionCache {
+            enabled: true,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl InstructionCache {
+    pub(super) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.clear();
+    }
+
+    pub(super) fn get(&self, address: Address) -> Option<Instruction> {
+        self.entries.get(address as usize).copied().flatten()
+    }
+
+    pub(super) fn insert(&mut self, address: Address, instruction: Instruction) {
+        let address = address as usize;
+        if !self.enabled || address >= CACHE_LIMIT {
+            return;
+        }
+
+        if address >= self.entries.len() {
+            self.entries.resize(address + 1, None);
+        }
+
+        self.entries[address] = Some(instruction);
+    }
+
+    /// Forget the instructions that include `address`.
+    pub(super) fn invalidate(&mut self, address: Address) {
+        let address = address as usize;
+        let start = address.saturating_sub(MAX_WIDTH - 1);
+        let end = (address + 1).min(self.entries.len());
+        if start < end {
+            self.entries[start..end].fill(None);
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,464 @@
+//! Control-flow graph of the code reachable from address 0, or from other
+//! entry points.
+//!
+//! Calls and returns are recognized from the stack idiom used by compiled
+//! intcode programs (and by the `call` and `ret` macros of `asm`): a call
+//! writes its return address on the stack at the relative base, then jumps
+//! to an immediate address; a return jumps to an address read relative to
+//! the relative base.
+
+use super::disasm::{self, DecodedInstruction};
+use super::{Opcode, ParameterMode, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Write};
+
+/// Instructions always executed one after the other: only the first one can
+/// be jumped to, and only the last one can jump.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub instructions: Vec<DecodedInstruction>,
+}
+
+impl BasicBlock {
+    pub fn start(&self) -> usize {
+        self.instructions[0].address
+    }
+
+    /// Address following the last instruction.
+    pub fn end(&self) -> usize {
+        self.last().next_address()
+    }
+
+    pub fn last(&self) -> &DecodedInstruction {
+        self.instructions.last().unwrap()
+    }
+
+    /// Whether the block ends with a call: the return address is pushed on
+    /// the stack, then the last instruction jumps to the function.
+    pub fn is_call(&self) -> bool {
+        let last = self.last();
+        last.is_unconditional_jump()
+            && last.jump_target().is_some()
+            && self.instructions.iter().any(|instruction| {
+                instruction.constant_result() == Some(last.next_address() as Value)
+                    && instruction.operands.last().map(|operand| operand.mode)
+                        == Some(ParameterMode::Relative)
+            })
+    }
+
+    /// Whether the block ends with a return: an unconditional jump to an
+    /// address read from the stack.
+    pub fn is_return(&self) -> bool {
+        let last = self.last();
+        last.is_unconditional_jump() && last.operands[1].mode == ParameterMode::Relative
+    }
+
+    /// Whether the block ends with a jump whose target cannot be known
+    /// statically, and which does not look like a return.
+    pub fn is_indirect(&self) -> bool {
+        let last = self.last();
+        last.may_jump() && last.jump_target().is_none() && !self.is_return()
+    }
+}
+
+impl fmt::Display for BasicBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for instruction in &self.instructions {
+            writeln!(f, "{:>5}: {}", instruction.address, instruction)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EdgeKind {
+    /// To the next block, when the last instruction does not jump.
+    FallThrough,
+    /// Conditional jump, when it is taken.
+    Branch,
+    /// Unconditional jump, other than a call or a return.
+    Jump,
+    /// From the caller to the function.
+    Call,
+    /// From the caller to the instruction following the call, where the
+    /// function returns.
+    CallReturn,
+    /// From a block returning from a function to the return sites of its
+    /// callers.
+    Return,
+    /// From a jump through a jump table to every entry of the table.
+    Table,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+/// An instruction writing to an address holding reachable code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WriteToCode {
+    /// Address of the instruction writing.
+    pub address: usize,
+    pub target: usize,
+    /// Address of the instruction including the target.
+    pub instruction: usize,
+}
+
+/// Jump table read by the jump ending a block, see `disasm::table_jumps`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpTable {
+    /// Address of the first entry.
+    pub start: usize,
+    pub targets: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cfg {
+    /// Basic blocks, by start address.
+    pub blocks: BTreeMap<usize, BasicBlock>,
+    pub edges: Vec<Edge>,
+    /// Blocks of every function, by entry address. The code at address 0 is
+    /// a function too.
+    pub functions: BTreeMap<usize, BTreeSet<usize>>,
+    /// Jump tables, by start of the block jumping through them.
+    pub tables: BTreeMap<usize, JumpTable>,
+}
+
+impl Cfg {
+    pub fn new(program: &[Value]) -> Self {
+        Cfg::with_entries(program, &[0])
+    }
+
+    /// Graph of the code reachable from any of `entries`, which are functions
+    /// too. See `disasm::reachable_from`.
+    pub fn with_entries(program: &[Value], entries: &[usize]) -> Self {
+        let code = disasm::reachable_from(program, entries);
+        let tables: Vec<(usize, JumpTable)> = disasm::table_jumps(&code)
+            .into_iter()
+            .map(|(jump, start)| {
+                let targets = disasm::jump_table(program, start);
+                (jump, JumpTable { start, targets })
+            })
+            .collect();
+        let mut starts = entries.to_vec();
+        for (_, table) in &tables {
+            starts.extend(&table.targets);
+        }
+        let leaders = leaders(&code, &starts);
+
+        let mut blocks = BTreeMap::new();
+        for &start in &leaders {
+            let mut instructions = Vec::new();
+            let mut address = start;
+            while let Some(instruction) = code.get(&address) {
+                if address != start && leaders.contains(&address) {
+                    break;
+                }
+
+                instructions.push(instruction.clone());
+                address = instruction.next_address();
+                if let Opcode::JumpIfTrue | Opcode::JumpIfFalse | Opcode::Halt = instruction.opcode
+                {
+                    break;
+                }
+            }
+
+            blocks.insert(start, BasicBlock { instructions });
+        }
+
+        let mut cfg = Cfg {
+            blocks,
+            edges: Vec::new(),
+            functions: BTreeMap::new(),
+            tables: BTreeMap::new(),
+        };
+        cfg.add_local_edges();
+        cfg.add_table_edges(tables);
+        cfg.find_functions(entries);
+        cfg.add_return_edges();
+        cfg.edges.sort();
+        cfg.edges.dedup();
+        cfg
+    }
+
+    /// Block including `address`, if it is the address of an instruction.
+    pub fn block_at(&self, address: usize) -> Option<&BasicBlock> {
+        self.blocks
+            .range(..=address)
+            .next_back()
+            .map(|(_, block)| block)
+            .filter(|block| {
+                block
+                    .instructions
+                    .iter()
+                    .any(|instruction| instruction.address == address)
+            })
+    }
+
+    /// Instruction including `address`, as its opcode or a parameter.
+    pub fn instruction_including(&self, address: usize) -> Option<&DecodedInstruction> {
+        let (_, block) = self.blocks.range(..=address).next_back()?;
+        block.instructions.iter().find(|instruction| {
+            (instruction.address..instruction.next_address()).contains(&address)
+        })
+    }
+
+    /// Instructions whose write operand targets reachable code. Only writes
+    /// in position mode are known statically: relative writes are assumed to
+    /// go to the stack.
+    pub fn writes_to_code(&self) -> Vec<WriteToCode> {
+        let mut writes = Vec::new();
+        for block in self.blocks.values() {
+            for instruction in &block.instructions {
+                let operand = match instruction.operands.last() {
+                    Some(operand) if instruction.opcode.writes_memory() => operand,
+                    _ => continue,
+                };
+                if operand.mode != ParameterMode::Position || operand.value.is_negative() {
+                    continue;
+                }
+
+                let target = operand.value as usize;
+                if let Some(written) = self.instruction_including(target) {
+                    writes.push(WriteToCode {
+                        address: instruction.address,
+                        target,
+                        instruction: written.address,
+                    });
+                }
+            }
+        }
+
+        writes
+    }
+
+    pub fn successors(&self, block: usize) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |edge| edge.from == block)
+    }
+
+    pub fn predecessors(&self, block: usize) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |edge| edge.to == block)
+    }
+
+    /// Addresses of the values that are not part of any reachable instruction.
+    pub fn unreachable(&self, len: usize) -> Vec<usize> {
+        let mut covered = vec![false; len];
+        for block in self.blocks.values() {
+            let end = block.end().min(len);
+            if block.start() < end {
+                covered[block.start()..end].fill(true);
+            }
+        }
+
+        (0..len).filter(|&address| !covered[address]).collect()
+    }
+
+    /// Graphviz DOT representation, with a cluster per function.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        self.write_dot(&mut dot).unwrap();
+        dot
+    }
+
+    fn write_dot(&self, f: &mut String) -> fmt::Result {
+        writeln!(f, "digraph cfg {{")?;
+        writeln!(f, "    node [shape=box, fontname=monospace];")?;
+
+        let mut placed = BTreeSet::new();
+        for (entry, blocks) in &self.functions {
+            writeln!(f, "    subgraph cluster_{} {{", entry)?;
+            writeln!(f, "        label=\"function {}\";", entry)?;
+            for &start in blocks {
+                if placed.insert(start) {
+                    write!(f, "        ")?;
+                    self.write_node(f, start)?;
+                }
+            }
+            writeln!(f, "    }}")?;
+        }
+
+        for &start in self.blocks.keys() {
+            if !placed.contains(&start) {
+                write!(f, "    ")?;
+                self.write_node(f, start)?;
+            }
+        }
+
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::FallThrough => "",
+                EdgeKind::Branch => " [color=darkgreen]",
+                EdgeKind::Jump => " [color=black]",
+                EdgeKind::Call => " [color=blue, style=bold]",
+                EdgeKind::CallReturn => " [style=dashed]",
+                EdgeKind::Return => " [color=red, style=dotted]",
+                EdgeKind::Table => " [color=purple]",
+            };
+            writeln!(f, "    b{} -> b{}{};", edge.from, edge.to, style)?;
+        }
+
+        writeln!(f, "}}")
+    }
+
+    fn write_node(&self, f: &mut String, start: usize) -> fmt::Result {
+        let block = &self.blocks[&start];
+        write!(f, "b{} [label=\"", start)?;
+        for instruction in &block.instructions {
+            write!(f, "{:>5}: {}\\l", instruction.address, instruction)?;
+        }
+        if block.is_indirect() {
+            write!(f, "(indirect jump)\\l")?;
+        }
+        writeln!(f, "\"];")
+    }
+
+    fn add_local_edges(&mut self) {
+        let mut edges = Vec::new();
+        for (&start, block) in &self.blocks {
+            let last = block.last();
+            let mut add = |to: usize, kind| {
+                if self.blocks.contains_key(&to) {
+                    edges.push(Edge {
+                        from: start,
+                        to,
+                        kind,
+                    });
+                }
+            };
+
+            if block.is_call() {
+                add(last.jump_target().unwrap(), EdgeKind::Call);
+                add(last.next_address(), EdgeKind::CallReturn);
+                continue;
+            }
+
+            if let Some(target) = last.jump_target() {
+                if last.is_unconditional_jump() {
+                    add(target, EdgeKind::Jump);
+                } else if last.may_jump() {
+                    add(target, EdgeKind::Branch);
+                }
+            }
+
+            if last.falls_through() {
+                add(block.end(), EdgeKind::FallThrough);
+            }
+        }
+
+        self.edges = edges;
+    }
+
+    fn add_table_edges(&mut self, tables: Vec<(usize, JumpTable)>) {
+        for (jump, table) in tables {
+            let from = match self.block_at(jump) {
+                Some(block) => block.start(),
+                None => continue,
+            };
+            for &to in &table.targets {
+                if self.blocks.contains_key(&to) {
+                    self.edges.push(Edge {
+                        from,
+                        to,
+                        kind: EdgeKind::Table,
+                    });
+                }
+            }
+
+            self.tables.insert(from, table);
+        }
+    }
+
+    /// Functions are the code at the entry points and the targets of calls.
+    /// Their blocks are those reachable from the entry without following calls
+    /// or returns.
+    fn find_functions(&mut self, roots: &[usize]) {
+        let mut entries: BTreeSet<usize> = self
+            .edges
+            .iter()
+            .filter(|edge| edge.kind == EdgeKind::Call)
+            .map(|edge| edge.to)
+            .collect();
+        entries.extend(
+            roots
+                .iter()
+                .filter(|&entry| self.blocks.contains_key(entry)),
+        );
+
+        for entry in entries {
+            let mut blocks = BTreeSet::new();
+            let mut queue = vec![entry];
+            while let Some(start) = queue.pop() {
+                if !blocks.insert(start) {
+                    continue;
+                }
+
+                queue.extend(
+                    self.successors(start)
+                        .filter(|edge| edge.kind != EdgeKind::Call)
+                        .map(|edge| edge.to),
+                );
+            }
+
+            self.functions.insert(entry, blocks);
+        }
+    }
+
+    fn add_return_edges(&mut self) {
+        let mut returns = Vec::new();
+        for (&entry, blocks) in &self.functions {
+            let return_sites: Vec<usize> = self
+                .edges
+                .iter()
+                .filter(|edge| edge.kind == EdgeKind::Call && edge.to == entry)
+                .map(|edge| self.blocks[&edge.from].end())
+                .filter(|site| self.blocks.contains_key(site))
+                .collect();
+
+            for &start in blocks {
+                if self.blocks[&start].is_return() {
+                    returns.extend(return_sites.iter().map(|&to| Edge {
+                        from: start,
+                        to,
+                        kind: EdgeKind::Return,
+                    }));
+                }
+            }
+        }
+
+        self.edges.extend(returns);
+    }
+}
+
+/// Addresses where a block starts: the entry points, jump targets, return
+/// addresses (constants pushed before a call), the instructions following a
+/// jump or a halt, and those not following another instruction.
+fn leaders(code: &BTreeMap<usize, DecodedInstruction>, entries: &[usize]) -> BTreeSet<usize> {
+    let mut leaders: BTreeSet<usize> = entries.iter().copied().collect();
+
+    let mut previous_end = None;
+    for (&address, instruction) in code {
+        if previous_end != Some(address) {
+            leaders.insert(address);
+        }
+
+        leaders.extend(instruction.jump_target());
+        leaders.extend(
+            instruction
+                .constant_result()
+                .filter(|&value| !value.is_negative())
+                .map(|value| value as usize),
+        );
+        if let Opcode::JumpIfTrue | Opcode::JumpIfFalse | Opcode::Halt = instruction.opcode {
+            leaders.insert(instruction.next_address());
+        }
+
+        previous_end = Some(instruction.next_address());
+    }
+
+    leaders.retain(|address| code.contains_key(address));
+    leaders
+}
@@ -0,0 +1,418 @@
+use super::cfg::Cfg;
+use super::disasm::DecodedInstruction;
+use super::{
+    to_address, Address, Computer, ErrorContext, Input, IntCodeErrorKind, IntCodeResult, Opcode,
+    Output, ParameterMode, Status, Value, Word,
+};
+use std::fmt;
+use std::sync::mpsc;
+
+/// Where an operand is read from, resolved when compiling.
+#[derive(Debug, Clone)]
+enum Source<W> {
+    Position(Address),
+    Immediate(W),
+    Relative(Address),
+}
+
+/// Where the last operand of an instruction writing to memory writes.
+#[derive(Debug, Clone, Copy)]
+enum Target {
+    Position(Address),
+    Relative(Address),
+}
+
+/// What to do after an instruction.
+enum Flow<W> {
+    Next,
+    /// Memory was written at this address.
+    Write(Address),
+    Output(W),
+    Jump(Address),
+    Halt,
+}
+
+type Handler<I, O, W> = fn(&mut Computer<I, O, W>, &Op<I, O, W>) -> IntCodeResult<Flow<W>>;
+
+/// A compiled instruction: the function executing its opcode, with its
+/// operands.
+struct Op<I, O, W> {
+    handler: Handler<I, O, W>,
+    address: Address,
+    next: Address,
+    sources: Vec<Source<W>>,
+    target: Option<Target>,
+}
+
+/// Instructions executed one after the other, from `start` up to `end`
+/// (excluded).
+struct Block<I, O, W> {
+    start: usize,
+    end: usize,
+    ops: Vec<Op<I, O, W>>,
+}
+
+/// A program translated ahead of time into basic blocks of threaded code: an
+/// array of instructions per block, each with its handler and its operands
+/// already decoded.
+///
+/// Only the code reachable from address 0 (see `cfg::Cfg`) is
+/// compiled, the rest is left to the interpreter. So is any block that does
+/// not match memory when `execute` starts, or that the program writes to
+/// while running: self-modifying programs still run correctly, only slower.
+pub struct CompiledProgram<I = mpsc::Receiver<Value>, O = mpsc::Sender<Value>, W = Value> {
+    program: Vec<W>,
+    blocks: Vec<Block<I, O, W>>,
+    /// Index of the block starting at each address.
+    block_starts: Vec<Option<usize>>,
+    /// Indexes of the blocks including each address.
+    covering: Vec<Vec<usize>>,
+}
+
+impl<I, O, W> fmt::Debug for CompiledProgram<I, O, W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CompiledProgram")
+            .field("len", &self.program.len())
+            .field("blocks", &self.blocks.len())
+            .finish()
+    }
+}
+
+impl<I: Input<W>, O: Output<W>, W: Word> CompiledProgram<I, O, W> {
+    pub fn new(program: &[W]) -> Self {
+        let values: Vec<Value> = program.iter().map(Word::saturating_value).collect();
+
+        let mut compiled = CompiledProgram {
+            program: program.to_vec(),
+            blocks: Vec::new(),
+            block_starts: vec![None; program.len()],
+            covering: vec![Vec::new(); program.len()],
+        };
+
+        for (&start, block) in &Cfg::new(&values).blocks {
+            let mut ops = Vec::new();
+            let mut address = start;
+            for instruction in &block.instructions {
+                let op = match compile(program, instruction) {
+                    Some(op) => op,
+                    None => break,
+                };
+                ops.push(op);
+                address = instruction.next_address();
+            }
+
+            if ops.is_empty() {
+                continue;
+            }
+
+            let idx = compiled.blocks.len();
+            compiled.block_starts[start] = Some(idx);
+            for covered in &mut compiled.covering[start..address] {
+                covered.push(idx);
+            }
+            compiled.blocks.push(Block {
+                start,
+                end: address,
+                ops,
+            });
+        }
+
+        compiled
+    }
+
+    /// Number of basic blocks compiled.
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Same as `Computer::execute`, for a computer whose memory holds the
+    /// compiled program, as after `load_program`. Instruction limits and
+    /// deadlines apply. When tracing, profiling, collecting coverage, recording
+    /// history or detecting self-modification, the interpreter runs the whole
+    /// program.
+    pub fn execute(&self, computer: &mut Computer<I, O, W>) -> IntCodeResult<()> {
+        if computer.instrumented() {
+            return computer.execute();
+        }
+
+        let mut dirty: Vec<bool> = self
+            .blocks
+            .iter()
+            .map(|block| {
+                (block.start..block.end)
+                    .any(|address| computer.memory.get(address) != self.program[address])
+            })
+            .collect();
+
+        loop {
+            let instruction_pointer = computer.instruction_pointer;
+            let block = self
+                .block_starts
+                .get(instruction_pointer as usize)
+                .copied()
+                .flatten()
+                .filter(|&idx| !dirty[idx]);
+
+            let halted = match block {
+                Some(idx) => self.run_block(computer, &self.blocks[idx], &mut dirty)?,
+                None => self.interpret(computer, &mut dirty)?,
+            };
+
+            if halted {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Run a block, returning whether the program halted.
+    fn run_block(
+        &self,
+        computer: &mut Computer<I, O, W>,
+        block: &Block<I, O, W>,
+        dirty: &mut [bool],
+    ) -> IntCodeResult<bool> {
+        for op in &block.ops {
+            let flow = computer
+                .check_limits()
+                .and_then(|_| (op.handler)(computer, op))
+                .map_err(|error| {
+                    error.with_context(ErrorContext::new(
+                        &computer.memory,
+                        op.address,
+                        computer.relative_base,
+                        computer.instruction_count,
+                    ))
+                })?;
+
+            match flow {
+                Flow::Next => computer.instruction_count += 1,
+                Flow::Write(address) => {
+                    computer.instruction_count += 1;
+                    if self.invalidate(address, dirty) {
+                        computer.instruction_pointer = op.next;
+                        return Ok(false);
+                    }
+                }
+                Flow::Output(value) => {
+                    computer.instruction_count += 1;
+                    computer.write_output(value, op.address)?;
+                }
+                Flow::Jump(target) => {
+                    computer.instruction_count += 1;
+                    computer.instruction_pointer = target;
+                    return Ok(false);
+                }
+                Flow::Halt => {
+                    computer.instruction_pointer = op.address;
+                    return Ok(true);
+                }
+            }
+        }
+
+        computer.instruction_pointer = block.end as Address;
+        Ok(false)
+    }
+
+    /// Execute a single instruction with the interpreter, returning whether
+    /// the program halted.
+    fn interpret(
+        &self,
+        computer: &mut Computer<I, O, W>,
+        dirty: &mut [bool],
+    ) -> IntCodeResult<bool> {
+        let instruction_pointer = computer.instruction_pointer;
+        match computer.execute_instruction(true)? {
+            Status::Output(value) => computer.write_output(value, instruction_pointer)?,
+            Status::Halted => return Ok(true),
+            Status::Running | Status::NeedsInput => (),
+        }
+
+        if let Some((address, _)) = computer.last_write {
+            self.invalidate(address, dirty);
+        }
+
+        Ok(false)
+    }
+
+    /// Mark the blocks including `address` as modified, returning whether
+    /// there are any.
+    fn invalidate(&self, address: Address, dirty: &mut [bool]) -> bool {
+        match self.covering.get(address as usize) {
+            Some(blocks) if !blocks.is_empty() => {
+                for &idx in blocks {
+                    dirty[idx] = true;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Compile an instruction, unless its operands are invalid, in which case the
+/// interpreter reports the error.
+fn compile<I: Input<W>, O: Output<W>, W: Word>(
+    program: &[W],
+    instruction: &DecodedInstruction,
+) -> Option<Op<I, O, W>> {
+    let operands = &program[instruction.address + 1..instruction.next_address()];
+    let mut sources = Vec::new();
+    let mut target = None;
+
+    for (idx, (operand, word)) in instruction.operands.iter().zip(operands).enumerate() {
+        if instruction.opcode.writes_memory() && idx + 1 == operands.len() {
+            let address = word.to_value()?;
+            target = Some(match operand.mode {
+                ParameterMode::Position => Target::Position(address),
+                ParameterMode::Immediate => return None,
+                ParameterMode::Relative => Target::Relative(address),
+            });
+        } else {
+            sources.push(match operand.mode {
+                ParameterMode::Position => Source::Position(word.to_value()?),
+                ParameterMode::Immediate => Source::Immediate(word.clone()),
+                ParameterMode::Relative => Source::Relative(word.to_value()?),
+            });
+        }
+    }
+
+    let handler: Handler<I, O, W> = match instruction.opcode {
+        Opcode::Add => add,
+        Opcode::Multiply => multiply,
+        Opcode::Input => input,
+        Opcode::Output => output,
+        Opcode::JumpIfTrue => jump_if_true,
+        Opcode::JumpIfFalse => jump_if_false,
+        Opcode::LessThan => less_than,
+        Opcode::Equals => equals,
+        Opcode::RelativeBaseOffset => relative_base_offset,
+        Opcode::Halt => halt,
+    };
+
+    Some(Op {
+        handler,
+        address: instruction.address as Address,
+        next: instruction.next_address() as Address,
+        sources,
+        target,
+    })
+}
+
+impl<I: Input<W>, O: Output<W>, W: Word> Computer<I, O, W> {
+    fn read_source(&self, source: &Source<W>) -> IntCodeResult<W> {
+        match source {
+            Source::Position(address) => self.memory.read(*address),
+            Source::Immediate(value) => Ok(value.clone()),
+            Source::Relative(offset) => self.memory.read(self.relative_address(*offset)?),
+        }
+    }
+
+    fn write_target(&mut self, target: Option<Target>, value: W) -> IntCodeResult<Flow<W>> {
+        let address = match target {
+            Some(Target::Position(address)) => address,
+            Some(Target::Relative(offset)) => self.relative_address(offset)?,
+            None => return Err(IntCodeErrorKind::WriteImmediateMode.into()),
+        };
+
+        self.write_address(address, value)?;
+        Ok(Flow::Write(address))
+    }
+}
+
+fn add<I: Input<W>, O: Output<W>, W: Word>(
+    computer: &mut Computer<I, O, W>,
+    op: &Op<I, O, W>,
+) -> IntCodeResult<Flow<W>> {
+    let a = computer.read_source(&op.sources[0])?;
+    let b = computer.read_source(&op.sources[1])?;
+    let sum = a.checked_add(&b).ok_or(IntCodeErrorKind::Overflow)?;
+    computer.write_target(op.target, sum)
+}
+
+fn multiply<I: Input<W>, O: Output<W>, W: Word>(
+    computer: &mut Computer<I, O, W>,
+    op: &Op<I, O, W>,
+) -> IntCodeResult<Flow<W>> {
+    let a = computer.read_source(&op.sources[0])?;
+    let b = computer.read_source(&op.sources[1])?;
+    let product = a.checked_mul(&b).ok_or(IntCodeErrorKind::Overflow)?;
+    computer.write_target(op.target, product)
+}
+
+fn input<I: Input<W>, O: Output<W>, W: Word>(
+    computer: &mut Computer<I, O, W>,
+    op: &Op<I, O, W>,
+) -> IntCodeResult<Flow<W>> {
+    let value = computer.input.read()?;
+    computer.write_target(op.target, value)
+}
+
+fn output<I: Input<W>, O: Output<W>, W: Word>(
+    computer: &mut Computer<I, O, W>,
+    op: &Op<I, O, W>,
+) -> IntCodeResult<Flow<W>> {
+    computer.read_source(&op.sources[0]).map(Flow::Output)
+}
+
+fn jump_if_true<I: Input<W>, O: Output<W>, W: Word>(
+    computer: &mut Computer<I, O, W>,
+    op: &Op<I, O, W>,
+) -> IntCodeResult<Flow<W>> {
+    let a = computer.read_source(&op.sources[0])?;
+    let b = computer.read_source(&op.sources[1])?;
+    if a.is_zero() {
+        Ok(Flow::Next)
+    } else {
+        to_address(&b).map(Flow::Jump)
+    }
+}
+
+fn jump_if_false<I: Input<W>, O: Output<W>, W: Word>(
+    computer: &mut Computer<I, O, W>,
+    op: &Op<I, O, W>,
+) -> IntCodeResult<Flow<W>> {
+    let a = computer.read_source(&op.sources[0])?;
+    let b = computer.read_source(&op.sources[1])?;
+    if a.is_zero() {
+        to_address(&b).map(Flow::Jump)
+    } else {
+        Ok(Flow::Next)
+    }
+}
+
+fn less_than<I: Input<W>, O: Output<W>, W: Word>(
+    computer: &mut Computer<I, O, W>,
+    op: &Op<I, O, W>,
+) -> IntCodeResult<Flow<W>> {
+    let a = computer.read_source(&op.sources[0])?;
+    let b = computer.read_source(&op.sources[1])?;
+    computer.write_target(op.target, W::from_value(if a < b { 1 } else { 0 }))
+}
+
+fn equals<I: Input<W>, O: Output<W>, W: Word>(
+    computer: &mut Computer<I, O, W>,
+    op: &Op<I, O, W>,
+) -> IntCodeResult<Flow<W>> {
+    let a = computer.read_source(&op.sources[0])?;
+    let b = computer.read_source(&op.sources[1])?;
+    computer.write_target(op.target, W::from_value(if a == b { 1 } else { 0 }))
+}
+
+fn relative_base_offset<I: Input<W>, O: Output<W>, W: Word>(
+    computer: &mut Computer<I, O, W>,
+    op: &Op<I, O, W>,
+) -> IntCodeResult<Flow<W>> {
+    let offset = computer.read_source(&op.sources[0])?;
+    computer.relative_base = offset
+        .to_value()
+        .and_then(|offset| computer.relative_base.checked_add(offset))
+        .ok_or(IntCodeErrorKind::Overflow)?;
+    Ok(Flow::Next)
+}
+
+fn halt<I: Input<W>, O: Output<W>, W: Word>(
+    _computer: &mut Computer<I, O, W>,
+    _op: &Op<I, O, W>,
+) -> IntCodeResult<Flow<W>> {
+    Ok(Flow::Halt)
+}
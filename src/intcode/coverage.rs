@@ -0,0 +1,100 @@
+use super::disasm::{self, Line};
+use super::{Address, Value};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+/// Addresses of the instructions executed by one or more runs of a program,
+/// with the number of executions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    hits: BTreeMap<Address, u64>,
+}
+
+impl Coverage {
+    /// Executions of the instruction at `address`.
+    pub fn hits(&self, address: Address) -> u64 {
+        self.hits.get(&address).copied().unwrap_or(0)
+    }
+
+    pub fn is_covered(&self, address: Address) -> bool {
+        self.hits(address) > 0
+    }
+
+    /// Addresses of the instructions executed, with their number of
+    /// executions.
+    pub fn covered(&self) -> impl Iterator<Item = (Address, u64)> + '_ {
+        self.hits.iter().map(|(&address, &hits)| (address, hits))
+    }
+
+    /// Add the executions of another run, of the same program.
+    pub fn merge(&mut self, other: &Coverage) {
+        for (&address, &hits) in &other.hits {
+            *self.hits.entry(address).or_insert(0) += hits;
+        }
+    }
+
+    /// Addresses of the instructions of the program never executed: the ones
+    /// found by `disasm::reachable`, or next to executed code.
+    pub fn uncovered(&self, program: &[Value]) -> Vec<Address> {
+        instructions(&self.listing(program))
+            .filter(|&address| !self.is_covered(address))
+            .collect()
+    }
+
+    /// Disassembly of the program with the executions of every instruction,
+    /// and `#####` in front of the ones never executed. Instructions are
+    /// decoded as loaded, so code modified by the program may show as data.
+    pub fn annotate(&self, program: &[Value]) -> String {
+        let listing = self.listing(program);
+        let total = instructions(&listing).count();
+        let covered = instructions(&listing)
+            .filter(|&address| self.is_covered(address))
+            .count();
+
+        let mut annotated = String::new();
+        writeln!(
+            annotated,
+            "Covered {} of {} instructions ({:.2}%)",
+            covered,
+            total,
+            100.0 * covered as f64 / total.max(1) as f64
+        )
+        .unwrap();
+        for line in &listing.lines {
+            let hits = match line {
+                Line::Instruction(instruction) => match self.hits(instruction.address as Address) {
+                    0 => "#####".to_string(),
+                    hits => hits.to_string(),
+                },
+                Line::Data { .. } => "-".to_string(),
+            };
+            writeln!(annotated, "{:>12} | {}", hits, listing.format_line(line)).unwrap();
+        }
+
+        annotated
+    }
+
+    pub(super) fn record(&mut self, address: Address) {
+        *self.hits.entry(address).or_insert(0) += 1;
+    }
+
+    /// Listing with the instructions executed, even when only reached by
+    /// indirect jumps.
+    fn listing(&self, program: &[Value]) -> disasm::Listing {
+        let mut entries = vec![0];
+        entries.extend(
+            self.covered()
+                .filter_map(|(address, _)| usize::try_from(address).ok()),
+        );
+        disasm::disassemble_from(program, &entries)
+    }
+}
+
+/// Addresses of the instructions of a listing.
+fn instructions(listing: &disasm::Listing) -> impl Iterator<Item = Address> + '_ {
+    listing.lines.iter().filter_map(|line| match line {
+        Line::Instruction(instruction) => Some(instruction.address as Address),
+        Line::Data { .. } => None,
+    })
+}
@@ -0,0 +1,1073 @@
+//! Decompiler from intcode to structured pseudo-Rust.
+//!
+//! Functions are those found by `Cfg`. Within a function, the relative base is
+//! followed from the entry, so relative-mode operands become local variables:
+//! `var1` is the first value above the return address, where callers pass the
+//! first argument. In `main`, the code at address 0, the relative base starts
+//! at 0 and relative operands are plain memory.
+//!
+//! Backward jumps become loops and forward conditional jumps become `if`
+//! statements. What does not fit that structure is left as `goto`. A jump
+//! through a jump table, see `disasm::table_jumps`, becomes a `match` of the
+//! entries.
+//!
+//! Code reached only once the program has patched an opcode, as day 5 does
+//! with its input, cannot be found statically: give its address as an extra
+//! entry point to `Cfg::with_entries`, for instance from a coverage file.
+
+use super::cfg::{Cfg, EdgeKind, JumpTable};
+use super::disasm::{DecodedInstruction, Operand};
+use super::{Opcode, ParameterMode, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+const INDENT: &str = "    ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Constant(Value),
+    Memory(Value),
+    /// Memory at a computed address.
+    Index(Box<Expr>),
+    Local(isize),
+    /// Relative to a relative base that is not known statically.
+    Relative(Value),
+    Add(Box<Expr>, Box<Expr>),
+    Multiply(Box<Expr>, Box<Expr>),
+    Negate(Box<Expr>),
+    Less(Box<Expr>, Box<Expr>),
+    Equal(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn add(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Constant(a), Expr::Constant(b)) if a.checked_add(b).is_some() => {
+                Expr::Constant(a + b)
+            }
+            (Expr::Constant(0), b) => b,
+            (a, Expr::Constant(0)) => a,
+            (Expr::Constant(a), b) => Expr::Add(Box::new(b), Box::new(Expr::Constant(a))),
+            (a, b) => Expr::Add(Box::new(a), Box::new(b)),
+        }
+    }
+
+    fn multiply(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Constant(a), Expr::Constant(b)) if a.checked_mul(b).is_some() => {
+                Expr::Constant(a * b)
+            }
+            (Expr::Constant(0), _) | (_, Expr::Constant(0)) => Expr::Constant(0),
+            (Expr::Constant(1), b) => b,
+            (a, Expr::Constant(1)) => a,
+            (Expr::Constant(-1), b) => Expr::Negate(Box::new(b)),
+            (a, Expr::Constant(-1)) => Expr::Negate(Box::new(a)),
+            (Expr::Constant(a), b) => Expr::Multiply(Box::new(b), Box::new(Expr::Constant(a))),
+            (a, b) => Expr::Multiply(Box::new(a), Box::new(b)),
+        }
+    }
+
+    fn is_compound(&self) -> bool {
+        matches!(
+            self,
+            Expr::Add(..) | Expr::Multiply(..) | Expr::Less(..) | Expr::Equal(..)
+        )
+    }
+}
+
+/// Operand of a binary operator, in parentheses unless it is simple.
+struct Term<'a>(&'a Expr);
+
+impl fmt::Display for Term<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_compound() {
+            write!(f, "({})", self.0)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Constant(value) => write!(f, "{}", value),
+            Expr::Memory(address) => write!(f, "mem[{}]", address),
+            Expr::Index(address) => write!(f, "mem[{}]", address),
+            Expr::Local(slot) if slot.is_negative() => write!(f, "var_m{}", -slot),
+            Expr::Local(slot) => write!(f, "var{}", slot),
+            Expr::Relative(offset) => write!(f, "rb[{}]", offset),
+            Expr::Add(a, b) => match **b {
+                Expr::Constant(b) if b.is_negative() => write!(f, "{} - {}", a, -b),
+                _ => write!(f, "{} + {}", a, Term(b)),
+            },
+            Expr::Multiply(a, b) => write!(f, "{} * {}", Term(a), Term(b)),
+            Expr::Negate(a) => write!(f, "-{}", Term(a)),
+            Expr::Less(a, b) => write!(f, "({} < {}) as isize", Term(a), Term(b)),
+            Expr::Equal(a, b) => write!(f, "({} == {}) as isize", Term(a), Term(b)),
+        }
+    }
+}
+
+/// Condition of an `if` or a `while`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    test: Test,
+    negated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Test {
+    NonZero(Expr),
+    Less(Expr, Expr),
+    Equal(Expr, Expr),
+}
+
+impl Condition {
+    pub fn negated(&self) -> Self {
+        Condition {
+            test: self.test.clone(),
+            negated: !self.negated,
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.test, self.negated) {
+            (Test::NonZero(a), false) => write!(f, "{} != 0", a),
+            (Test::NonZero(a), true) => write!(f, "{} == 0", a),
+            (Test::Less(a, b), false) => write!(f, "{} < {}", Term(a), Term(b)),
+            (Test::Less(a, b), true) => write!(f, "{} >= {}", Term(a), Term(b)),
+            (Test::Equal(a, b), false) => write!(f, "{} == {}", Term(a), Term(b)),
+            (Test::Equal(a, b), true) => write!(f, "{} != {}", Term(a), Term(b)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement {
+    /// An assignment, an output, a call or an indirect jump.
+    Simple(String),
+    Label(usize),
+    Goto(usize),
+    If {
+        condition: Condition,
+        then: Vec<Statement>,
+        otherwise: Vec<Statement>,
+    },
+    Loop(Vec<Statement>),
+    /// A jump through a jump table, to the target at `index`.
+    Switch {
+        index: String,
+        targets: Vec<usize>,
+    },
+    While {
+        condition: Condition,
+        body: Vec<Statement>,
+    },
+    Break,
+    Continue,
+    Return,
+    Halt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub entry: usize,
+    /// Slots of the local variables read before being written.
+    pub parameters: Vec<isize>,
+    pub body: Vec<Statement>,
+}
+
+impl Function {
+    pub fn name(&self) -> String {
+        function_name(self.entry)
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parameters: Vec<String> = self
+            .parameters
+            .iter()
+            .map(|&slot| Expr::Local(slot).to_string())
+            .collect();
+        writeln!(f, "fn {}({}) {{", self.name(), parameters.join(", "))?;
+        write_statements(f, &self.body, 1)?;
+        writeln!(f, "}}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decompiled {
+    pub functions: Vec<Function>,
+}
+
+impl fmt::Display for Decompiled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, function) in self.functions.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", function)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Decompile the code reachable from address 0.
+pub fn decompile(program: &[Value]) -> Decompiled {
+    decompile_cfg(&Cfg::new(program))
+}
+
+/// Decompile the functions of a control-flow graph, for instance one built
+/// with `Cfg::with_entries`.
+pub fn decompile_cfg(cfg: &Cfg) -> Decompiled {
+    let scratch = scratch_addresses(cfg);
+    let reads = position_reads(cfg);
+    let mut decompilers: Vec<FunctionDecompiler> = cfg
+        .functions
+        .iter()
+        .map(|(&entry, blocks)| FunctionDecompiler::new(cfg, entry, blocks, &scratch, &reads))
+        .collect();
+
+    let parameters: BTreeMap<usize, Vec<isize>> = decompilers
+        .iter()
+        .map(|decompiler| (decompiler.entry, decompiler.parameters()))
+        .collect();
+    for decompiler in &mut decompilers {
+        decompiler.callees = parameters.clone();
+    }
+
+    Decompiled {
+        functions: decompilers
+            .iter()
+            .map(|decompiler| Function {
+                entry: decompiler.entry,
+                parameters: parameters[&decompiler.entry].clone(),
+                body: decompiler.body(),
+            })
+            .collect(),
+    }
+}
+
+/// Addresses only read by conditional jumps, right after a comparison writing
+/// to them: compilers use them as scratch space for conditions.
+fn scratch_addresses(cfg: &Cfg) -> BTreeSet<Value> {
+    let mut scratch = BTreeSet::new();
+    let mut read = BTreeSet::new();
+    for block in cfg.blocks.values() {
+        for (idx, instruction) in block.instructions.iter().enumerate() {
+            for (operand_idx, operand) in instruction.operands.iter().enumerate() {
+                if operand.mode != ParameterMode::Position || is_write(instruction, operand_idx) {
+                    continue;
+                }
+
+                if is_tested(&block.instructions, idx, operand_idx) {
+                    scratch.insert(operand.value);
+                } else {
+                    read.insert(operand.value);
+                }
+            }
+        }
+    }
+
+    scratch.difference(&read).copied().collect()
+}
+
+/// Addresses read by some instruction with a position-mode operand.
+fn position_reads(cfg: &Cfg) -> BTreeSet<Value> {
+    let mut reads = BTreeSet::new();
+    for block in cfg.blocks.values() {
+        for instruction in &block.instructions {
+            for (operand_idx, operand) in instruction.operands.iter().enumerate() {
+                if operand.mode == ParameterMode::Position && !is_write(instruction, operand_idx) {
+                    reads.insert(operand.value);
+                }
+            }
+        }
+    }
+
+    reads
+}
+
+fn is_write(instruction: &DecodedInstruction, operand_idx: usize) -> bool {
+    instruction.opcode.writes_memory() && operand_idx + 1 == instruction.operands.len()
+}
+
+/// Whether the operand is the condition of a jump, written by the comparison
+/// just before it.
+fn is_tested(instructions: &[DecodedInstruction], idx: usize, operand_idx: usize) -> bool {
+    operand_idx == 0
+        && idx > 0
+        && tests_comparison(&instructions[idx - 1], &instructions[idx])
+        && instructions[idx - 1].operands[2] == instructions[idx].operands[0]
+}
+
+/// Whether `jump` is a conditional jump following `comparison`.
+fn tests_comparison(comparison: &DecodedInstruction, jump: &DecodedInstruction) -> bool {
+    matches!(comparison.opcode, Opcode::LessThan | Opcode::Equals)
+        && jump.may_jump()
+        && jump.falls_through()
+}
+
+pub(super) fn function_name(entry: usize) -> String {
+    if entry == 0 {
+        "main".to_string()
+    } else {
+        format!("func_{}", entry)
+    }
+}
+
+fn write_statements(f: &mut fmt::Formatter, statements: &[Statement], depth: usize) -> fmt::Result {
+    let indent = INDENT.repeat(depth);
+    for statement in statements {
+        match statement {
+            Statement::Simple(text) => writeln!(f, "{}{}", indent, text)?,
+            Statement::Label(address) => writeln!(f, "{}label_{}:", indent, address)?,
+            Statement::Goto(address) => writeln!(f, "{}goto label_{};", indent, address)?,
+            Statement::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                writeln!(f, "{}if {} {{", indent, condition)?;
+                write_statements(f, then, depth + 1)?;
+                if !otherwise.is_empty() {
+                    writeln!(f, "{}}} else {{", indent)?;
+                    write_statements(f, otherwise, depth + 1)?;
+                }
+                writeln!(f, "{}}}", indent)?;
+            }
+            Statement::Loop(body) => {
+                writeln!(f, "{}loop {{", indent)?;
+                write_statements(f, body, depth + 1)?;
+                writeln!(f, "{}}}", indent)?;
+            }
+            Statement::Switch { index, targets } => {
+                writeln!(f, "{}match {} {{", indent, index)?;
+                for (idx, target) in targets.iter().enumerate() {
+                    writeln!(f, "{}{}{} => goto label_{};", indent, INDENT, idx, target)?;
+                }
+                writeln!(f, "{}}}", indent)?;
+            }
+            Statement::While { condition, body } => {
+                writeln!(f, "{}while {} {{", indent, condition)?;
+                write_statements(f, body, depth + 1)?;
+                writeln!(f, "{}}}", indent)?;
+            }
+            Statement::Break => writeln!(f, "{}break;", indent)?,
+            Statement::Continue => writeln!(f, "{}continue;", indent)?,
+            Statement::Return => writeln!(f, "{}return;", indent)?,
+            Statement::Halt => writeln!(f, "{}halt();", indent)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// The part of a function being structured.
+#[derive(Debug, Clone, Copy)]
+struct Region {
+    end: usize,
+    /// Where execution continues after the region: jumping there from its last
+    /// block needs no statement.
+    follow: Option<usize>,
+    /// Header and exit of the innermost loop.
+    header: Option<usize>,
+    exit: Option<usize>,
+}
+
+struct FunctionDecompiler<'a> {
+    cfg: &'a Cfg,
+    entry: usize,
+    blocks: BTreeSet<usize>,
+    /// Relative base at the start of each block, from the function entry.
+    deltas: HashMap<usize, Option<isize>>,
+    /// Comparisons only used by the conditional jump following them.
+    folded: BTreeSet<usize>,
+    /// Operands written by the instruction just before them, by address.
+    patches: HashMap<usize, Expr>,
+    /// Instructions writing to the next one, which nothing else reads.
+    patchers: BTreeSet<usize>,
+    /// Parameters of every function, to show the arguments of calls.
+    callees: BTreeMap<usize, Vec<isize>>,
+}
+
+impl<'a> FunctionDecompiler<'a> {
+    fn new(
+        cfg: &'a Cfg,
+        entry: usize,
+        blocks: &BTreeSet<usize>,
+        scratch: &BTreeSet<Value>,
+        reads: &BTreeSet<Value>,
+    ) -> Self {
+        let mut decompiler = FunctionDecompiler {
+            cfg,
+            entry,
+            blocks: blocks.clone(),
+            deltas: HashMap::new(),
+            folded: BTreeSet::new(),
+            patches: HashMap::new(),
+            patchers: BTreeSet::new(),
+            callees: BTreeMap::new(),
+        };
+        decompiler.find_deltas();
+        decompiler.find_patches(reads);
+        decompiler.fold_comparisons(scratch);
+        decompiler
+    }
+
+    fn find_deltas(&mut self) {
+        let mut stack = vec![(self.entry, Some(0))];
+        while let Some((start, delta)) = stack.pop() {
+            if !self.blocks.contains(&start) || self.deltas.contains_key(&start) {
+                continue;
+            }
+
+            self.deltas.insert(start, delta);
+            let end = self
+                .instructions(start)
+                .last()
+                .map_or(delta, |&(instruction, delta)| {
+                    next_delta(instruction, delta)
+                });
+            stack.extend(
+                self.cfg
+                    .successors(start)
+                    .filter(|edge| edge.kind != EdgeKind::Call && edge.kind != EdgeKind::Return)
+                    .map(|edge| (edge.to, end)),
+            );
+        }
+    }
+
+    /// Instructions of a block, with the relative base before each of them.
+    fn instructions(&self, start: usize) -> Vec<(&'a DecodedInstruction, Option<isize>)> {
+        let mut delta = self.deltas.get(&start).copied().flatten();
+        let mut instructions = Vec::new();
+        for instruction in &self.cfg.blocks[&start].instructions {
+            instructions.push((instruction, delta));
+            delta = next_delta(instruction, delta);
+        }
+
+        instructions
+    }
+
+    /// Local variables read and written, in address order.
+    fn accesses(&self) -> Vec<(isize, bool)> {
+        let mut accesses = Vec::new();
+        for &start in &self.blocks {
+            for (instruction, delta) in self.instructions(start) {
+                let delta = match delta {
+                    Some(delta) => delta,
+                    None => continue,
+                };
+                let writes = instruction.opcode.writes_memory();
+                for (idx, operand) in instruction.operands.iter().enumerate() {
+                    if operand.mode == ParameterMode::Relative {
+                        let write = writes && idx + 1 == instruction.operands.len();
+                        accesses.push((delta + operand.value, write));
+                    }
+                }
+            }
+        }
+
+        accesses
+    }
+
+    fn parameters(&self) -> Vec<isize> {
+        if self.entry == 0 {
+            return Vec::new();
+        }
+
+        let mut first = BTreeMap::new();
+        for (slot, write) in self.accesses() {
+            first.entry(slot).or_insert(write);
+        }
+
+        first
+            .into_iter()
+            .filter(|&(slot, write)| slot > 0 && !write)
+            .map(|(slot, _)| slot)
+            .collect()
+    }
+
+    /// Find the comparisons into a scratch address or local variable, which
+    /// the conditional jump right after them then tests directly.
+    fn fold_comparisons(&mut self, scratch: &BTreeSet<Value>) {
+        let mut tested = BTreeSet::new();
+        let mut read = BTreeSet::new();
+        for &start in &self.blocks {
+            let block = &self.cfg.blocks[&start].instructions;
+            for (idx, (instruction, delta)) in self.instructions(start).into_iter().enumerate() {
+                let delta = match delta {
+                    Some(delta) => delta,
+                    None => continue,
+                };
+                for (operand_idx, operand) in instruction.operands.iter().enumerate() {
+                    if operand.mode != ParameterMode::Relative || is_write(instruction, operand_idx)
+                    {
+                        continue;
+                    }
+
+                    if is_tested(block, idx, operand_idx) {
+                        tested.insert(delta + operand.value);
+                    } else {
+                        read.insert(delta + operand.value);
+                    }
+                }
+            }
+        }
+
+        for &start in &self.blocks {
+            let instructions = self.instructions(start);
+            let (comparison, delta, jump) = match instructions[..] {
+                [.., (comparison, delta), (jump, _)] => (comparison, delta, jump),
+                _ => continue,
+            };
+            if !tests_comparison(comparison, jump) {
+                continue;
+            }
+
+            let result = comparison.operands[2];
+            let unused = match (result.mode, delta) {
+                (ParameterMode::Position, _) => scratch.contains(&result.value),
+                (ParameterMode::Relative, Some(delta)) if self.entry != 0 => {
+                    let slot = delta + result.value;
+                    tested.contains(&slot) && !read.contains(&slot)
+                }
+                _ => false,
+            };
+            if unused {
+                self.folded.insert(comparison.address);
+            }
+        }
+    }
+
+    /// Find the instructions computing an operand of the next instruction,
+    /// which is how programs access memory at a computed address.
+    fn find_patches(&mut self, reads: &BTreeSet<Value>) {
+        for &start in &self.blocks {
+            let instructions = self.instructions(start);
+            for pair in instructions.windows(2) {
+                let ((writer, delta), (patched, _)) = (pair[0], pair[1]);
+                let value = match self.value(writer, delta) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                let target = writer.operands[2];
+                if target.mode != ParameterMode::Position
+                    || target.value <= patched.address as Value
+                    || target.value >= patched.next_address() as Value
+                {
+                    continue;
+                }
+
+                // The control-flow graph relies on the jump targets as decoded.
+                let address = target.value as usize;
+                let idx = address - patched.address - 1;
+                if patched.may_jump() && idx == 1 {
+                    continue;
+                }
+
+                // When something else reads the operand, the write stays, and
+                // the operand is read back: the value written may depend on
+                // what it overwrites.
+                let shared = reads.contains(&target.value);
+                let value = if shared {
+                    Expr::Memory(target.value)
+                } else {
+                    value
+                };
+                let patch = match (patched.operands[idx].mode, value) {
+                    (ParameterMode::Position, Expr::Constant(address)) => Expr::Memory(address),
+                    (ParameterMode::Position, value) => Expr::Index(Box::new(value)),
+                    (ParameterMode::Immediate, value) => value,
+                    (ParameterMode::Relative, _) => continue,
+                };
+                self.patches.insert(address, patch);
+                if !shared {
+                    self.patchers.insert(writer.address);
+                }
+            }
+        }
+    }
+
+    /// Operand `idx` of an instruction, with the value written to it just
+    /// before if any.
+    fn operand(&self, instruction: &DecodedInstruction, idx: usize, delta: Option<isize>) -> Expr {
+        match self.patches.get(&(instruction.address + 1 + idx)) {
+            Some(patch) => patch.clone(),
+            None => self.expr(&instruction.operands[idx], delta),
+        }
+    }
+
+    fn expr(&self, operand: &Operand, delta: Option<isize>) -> Expr {
+        match operand.mode {
+            ParameterMode::Position => Expr::Memory(operand.value),
+            ParameterMode::Immediate => Expr::Constant(operand.value),
+            ParameterMode::Relative => self.relative(operand.value, delta),
+        }
+    }
+
+    fn relative(&self, offset: Value, delta: Option<isize>) -> Expr {
+        match delta {
+            Some(delta) if self.entry == 0 => Expr::Memory(delta + offset),
+            Some(delta) => Expr::Local(delta + offset),
+            None => Expr::Relative(offset),
+        }
+    }
+
+    /// Value computed by an arithmetic or comparison instruction.
+    fn value(&self, instruction: &DecodedInstruction, delta: Option<isize>) -> Option<Expr> {
+        let a = self.operand(instruction, 0, delta);
+        let b = || self.operand(instruction, 1, delta);
+        match instruction.opcode {
+            Opcode::Add => Some(Expr::add(a, b())),
+            Opcode::Multiply => Some(Expr::multiply(a, b())),
+            Opcode::LessThan => Some(Expr::Less(Box::new(a), Box::new(b()))),
+            Opcode::Equals => Some(Expr::Equal(Box::new(a), Box::new(b()))),
+            _ => None,
+        }
+    }
+
+    fn statement(&self, instruction: &DecodedInstruction, delta: Option<isize>) -> Option<String> {
+        let operand = |idx| self.operand(instruction, idx, delta);
+        match instruction.opcode {
+            Opcode::Input => Some(format!("{} = input();", operand(0))),
+            Opcode::Output => Some(format!("output({});", operand(0))),
+            Opcode::RelativeBaseOffset => match (delta, operand(0)) {
+                (Some(_), Expr::Constant(_)) => None,
+                (_, offset) => Some(format!("rb += {};", offset)),
+            },
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse | Opcode::Halt => None,
+            _ => {
+                let value = self.value(instruction, delta)?;
+                let target = operand(2);
+                if value == target {
+                    None
+                } else {
+                    Some(assignment(&target, value))
+                }
+            }
+        }
+    }
+
+    /// Condition for the conditional jump at the end of a block to be taken.
+    fn condition(&self, start: usize) -> Condition {
+        let instructions = self.instructions(start);
+        let (jump, delta) = instructions[instructions.len() - 1];
+        let test = match instructions[..] {
+            [.., (comparison, delta), _] if self.folded.contains(&comparison.address) => {
+                let a = self.operand(comparison, 0, delta);
+                let b = self.operand(comparison, 1, delta);
+                if comparison.opcode == Opcode::LessThan {
+                    Test::Less(a, b)
+                } else {
+                    Test::Equal(a, b)
+                }
+            }
+            _ => Test::NonZero(self.operand(jump, 0, delta)),
+        };
+
+        Condition {
+            test,
+            negated: jump.opcode == Opcode::JumpIfFalse,
+        }
+    }
+
+    fn call(&self, start: usize) -> String {
+        let instructions = self.instructions(start);
+        let (jump, delta) = instructions[instructions.len() - 1];
+        let target = jump.jump_target().unwrap();
+        let arguments: Vec<String> = self
+            .callees
+            .get(&target)
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .map(|&slot| self.relative(slot, delta).to_string())
+            .collect();
+        format!("{}({});", function_name(target), arguments.join(", "))
+    }
+
+    fn body(&self) -> Vec<Statement> {
+        let first = match self.blocks.iter().next() {
+            Some(&first) => first,
+            None => return Vec::new(),
+        };
+        let end = self
+            .blocks
+            .iter()
+            .map(|start| self.cfg.blocks[start].end())
+            .max()
+            .unwrap();
+
+        let mut body = Vec::new();
+        if first != self.entry {
+            body.push(Statement::Goto(self.entry));
+        }
+        body.extend(self.structure(
+            first,
+            Region {
+                end,
+                follow: None,
+                header: None,
+                exit: None,
+            },
+            None,
+        ));
+
+        let mut targets = BTreeSet::new();
+        goto_targets(&body, &mut targets);
+        simplify(body, &targets)
+    }
+
+    fn structure(&self, start: usize, region: Region, header: Option<usize>) -> Vec<Statement> {
+        let mut statements = Vec::new();
+        let mut address = start;
+        while let Some(&block) = self
+            .blocks
+            .range(address.min(region.end)..region.end)
+            .next()
+        {
+            if header != Some(block) {
+                if let Some(end) = self.loop_end(block, region.end) {
+                    let body_region = Region {
+                        end,
+                        follow: Some(block),
+                        header: Some(block),
+                        exit: Some(end),
+                    };
+                    let mut body = self.structure(block, body_region, Some(block));
+                    let latch = self.blocks.range(..end).next_back().unwrap();
+                    if self.cfg.blocks[latch].last().falls_through() {
+                        body.push(Statement::Break);
+                    }
+
+                    statements.push(Statement::Loop(body));
+                    address = end;
+                    continue;
+                }
+            }
+
+            statements.push(Statement::Label(block));
+            address = self.structure_block(block, region, &mut statements);
+        }
+
+        statements
+    }
+
+    /// End of the loop starting at `header`, if some block before `end` jumps
+    /// back to it.
+    fn loop_end(&self, header: usize, end: usize) -> Option<usize> {
+        self.cfg
+            .predecessors(header)
+            .filter(|edge| edge.kind == EdgeKind::Branch || edge.kind == EdgeKind::Jump)
+            .filter(|edge| edge.from >= header && self.blocks.contains(&edge.from))
+            .map(|edge| self.cfg.blocks[&edge.from].end())
+            .filter(|&latch_end| latch_end <= end)
+            .max()
+    }
+
+    /// Add the statements of a block, returning where to continue.
+    fn structure_block(
+        &self,
+        start: usize,
+        region: Region,
+        statements: &mut Vec<Statement>,
+    ) -> usize {
+        let block = &self.cfg.blocks[&start];
+        let last = block.last();
+        let next = block.end();
+        // A block may end past its region, when its last instruction overlaps the
+        // next one.
+        let is_last = self
+            .blocks
+            .range(next.min(region.end)..region.end)
+            .next()
+            .is_none();
+
+        let pushed_return = if block.is_call() {
+            block
+                .instructions
+                .iter()
+                .find(|instruction| instruction.constant_result() == Some(next as Value))
+                .map(|instruction| instruction.address)
+        } else {
+            None
+        };
+        for (instruction, delta) in self.instructions(start) {
+            if Some(instruction.address) == pushed_return
+                || self.folded.contains(&instruction.address)
+                || self.patchers.contains(&instruction.address)
+            {
+                continue;
+            }
+
+            statements.extend(self.statement(instruction, delta).map(Statement::Simple));
+        }
+
+        match last.opcode {
+            Opcode::Halt => {
+                statements.push(Statement::Halt);
+                return next;
+            }
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse if last.may_jump() => (),
+            _ => return next,
+        }
+
+        if block.is_call() {
+            statements.push(Statement::Simple(self.call(start)));
+            return next;
+        }
+        if block.is_return() {
+            statements.push(Statement::Return);
+            return next;
+        }
+
+        let unconditional = last.is_unconditional_jump();
+        let target = match last.jump_target() {
+            Some(target) => target,
+            None => {
+                let delta = self.instructions(start).last().unwrap().1;
+                let jump = match self.cfg.tables.get(&start) {
+                    Some(table) => self.switch(last, table),
+                    None => Statement::Simple(format!("goto {};", self.operand(last, 1, delta))),
+                };
+                if unconditional {
+                    statements.push(jump);
+                } else {
+                    statements.push(Statement::If {
+                        condition: self.condition(start),
+                        then: vec![jump],
+                        otherwise: Vec::new(),
+                    });
+                }
+                return next;
+            }
+        };
+
+        if unconditional {
+            statements.extend(self.jump(target, next, region, is_last));
+            return next;
+        }
+
+        let leaves = region.header == Some(target) || region.exit == Some(target);
+        if !leaves && next < target && target <= region.end {
+            // The code between the jump and its target runs when it is not
+            // taken. If that code ends by jumping further, over the code at the
+            // target, the latter is the `else` part.
+            let mut join = target;
+            if let Some(&tail) = self.blocks.range(next..target).next_back() {
+                let tail = &self.cfg.blocks[&tail];
+                if let Some(after) = tail.last().jump_target() {
+                    if tail.last().is_unconditional_jump()
+                        && !tail.is_call()
+                        && target < after
+                        && after <= region.end
+                    {
+                        join = after;
+                    }
+                }
+            }
+
+            let then = self.structure(
+                next,
+                Region {
+                    end: target,
+                    follow: Some(join),
+                    ..region
+                },
+                None,
+            );
+            let otherwise = if join != target {
+                self.structure(
+                    target,
+                    Region {
+                        end: join,
+                        follow: Some(join),
+                        ..region
+                    },
+                    None,
+                )
+            } else {
+                Vec::new()
+            };
+
+            statements.push(Statement::If {
+                condition: self.condition(start).negated(),
+                then,
+                otherwise,
+            });
+            return join;
+        }
+
+        let then = self.jump(target, next, region, false);
+        if !then.is_empty() {
+            statements.push(Statement::If {
+                condition: self.condition(start),
+                then,
+                otherwise: Vec::new(),
+            });
+        }
+        next
+    }
+
+    /// Jump through a jump table: the operand of the jump, at `slot`, holds
+    /// the address of an entry.
+    fn switch(&self, jump: &DecodedInstruction, table: &JumpTable) -> Statement {
+        let start = table.start as Value;
+        let slot = jump.address + 2;
+        let entry = match self.patches.get(&slot) {
+            Some(patch) => patch.clone(),
+            None => Expr::Memory(slot as Value),
+        };
+        let index = match entry {
+            Expr::Add(a, b) if *b == Expr::Constant(start) => *a,
+            entry => Expr::add(entry, Expr::Constant(-start)),
+        };
+        Statement::Switch {
+            index: index.to_string(),
+            targets: table.targets.clone(),
+        }
+    }
+
+    fn jump(&self, target: usize, next: usize, region: Region, is_last: bool) -> Vec<Statement> {
+        let falls_to = self.blocks.range(next.min(region.end)..region.end).next() == Some(&target);
+        if falls_to || is_last && region.follow == Some(target) {
+            Vec::new()
+        } else if region.header == Some(target) {
+            vec![Statement::Continue]
+        } else if region.exit == Some(target) {
+            vec![Statement::Break]
+        } else {
+            vec![Statement::Goto(target)]
+        }
+    }
+}
+
+fn next_delta(instruction: &DecodedInstruction, delta: Option<isize>) -> Option<isize> {
+    if instruction.opcode != Opcode::RelativeBaseOffset {
+        return delta;
+    }
+
+    match instruction.operands[0].immediate() {
+        Some(offset) => delta.and_then(|delta| delta.checked_add(offset)),
+        None => None,
+    }
+}
+
+fn assignment(target: &Expr, value: Expr) -> String {
+    match value {
+        Expr::Add(a, b) if *a == *target => match *b {
+            Expr::Constant(b) if b.is_negative() => format!("{} -= {};", target, -b),
+            b => format!("{} += {};", target, b),
+        },
+        Expr::Multiply(a, b) if *a == *target => format!("{} *= {};", target, b),
+        value => format!("{} = {};", target, value),
+    }
+}
+
+fn goto_targets(statements: &[Statement], targets: &mut BTreeSet<usize>) {
+    for statement in statements {
+        match statement {
+            Statement::Goto(target) => {
+                targets.insert(*target);
+            }
+            Statement::Switch {
+                targets: switch, ..
+            } => targets.extend(switch),
+            Statement::If {
+                then, otherwise, ..
+            } => {
+                goto_targets(then, targets);
+                goto_targets(otherwise, targets);
+            }
+            Statement::Loop(body) | Statement::While { body, .. } => goto_targets(body, targets),
+            _ => (),
+        }
+    }
+}
+
+/// Remove the labels no `goto` uses, and turn loops starting with a `break`
+/// into `while` loops.
+fn simplify(statements: Vec<Statement>, targets: &BTreeSet<usize>) -> Vec<Statement> {
+    let mut simplified = Vec::new();
+    for statement in statements {
+        match statement {
+            Statement::Label(address) if !targets.contains(&address) => (),
+            Statement::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let then = simplify(then, targets);
+                let otherwise = simplify(otherwise, targets);
+                if then.is_empty() && otherwise.is_empty() {
+                    continue;
+                }
+
+                simplified.push(if then.is_empty() {
+                    Statement::If {
+                        condition: condition.negated(),
+                        then: otherwise,
+                        otherwise: then,
+                    }
+                } else {
+                    Statement::If {
+                        condition,
+                        then,
+                        otherwise,
+                    }
+                });
+            }
+            Statement::Loop(body) => {
+                let mut body = simplify(body, targets);
+                end_loop(&mut body);
+                simplified.push(match body.first() {
+                    Some(Statement::If {
+                        condition,
+                        then,
+                        otherwise,
+                    }) if then[..] == [Statement::Break] && otherwise.is_empty() => {
+                        Statement::While {
+                            condition: condition.negated(),
+                            body: body.split_off(1),
+                        }
+                    }
+                    _ => Statement::Loop(body),
+                });
+            }
+            statement => simplified.push(statement),
+        }
+    }
+
+    simplified
+}
+
+/// Tidy the end of a loop body: `continue` is implicit there, and a loop that
+/// continues only if a condition holds breaks if it does not.
+fn end_loop(body: &mut Vec<Statement>) {
+    if body.last() == Some(&Statement::Continue) {
+        body.pop();
+    }
+
+    if let [.., Statement::If {
+        condition,
+        then,
+        otherwise,
+    }, Statement::Break] = &body[..]
+    {
+        if then[..] == [Statement::Continue] && otherwise.is_empty() {
+            let condition = condition.negated();
+            body.truncate(body.len() - 2);
+            body.push(Statement::If {
+                condition,
+                then: vec![Statement::Break],
+                otherwise: Vec::new(),
+            });
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use super::{Instruction, Opcode, ParameterMode, Value, Word};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+
+const DATA_PER_LINE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operand<W = Value> {
+    pub mode: ParameterMode,
+    pub value: W,
+}
+
+impl<W: Clone> Operand<W> {
+    pub fn immediate(&self) -> Option<W> {
+        Some(self.value.clone()).filter(|_| self.mode == ParameterMode::Immediate)
+    }
+}
+
+impl<W: Word> fmt::Display for Operand<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mode {
+            ParameterMode::Position => write!(f, "[{}]", self.value),
+            ParameterMode::Immediate => write!(f, "#{}", self.value),
+            ParameterMode::Relative if self.value < W::from_value(0) => {
+                write!(f, "rb{}", self.value)
+            }
+            ParameterMode::Relative => write!(f, "rb+{}", self.value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    pub address: usize,
+    pub opcode: Opcode,
+    pub operands: Vec<Operand>,
+}
+
+impl DecodedInstruction {
+    /// Number of memory cells taken by the instruction and its operands.
+    pub fn width(&self) -> usize {
+        1 + self.operands.len()
+    }
+
+    pub fn next_address(&self) -> usize {
+        self.address + self.width()
+    }
+
+    /// Target of a jump, if it is known statically (immediate mode).
+    pub fn jump_target(&self) -> Option<usize> {
+        match self.opcode {
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => self.operands[1]
+                .immediate()
+                .filter(|target| !target.is_negative())
+                .map(|target| target as usize),
+            _ => None,
+        }
+    }
+
+    /// Whether the instruction is a jump that can be taken.
+    pub fn may_jump(&self) -> bool {
+        match self.opcode {
+            Opcode::JumpIfTrue => self.condition().is_none_or(|c| c != 0),
+            Opcode::JumpIfFalse => self.condition().is_none_or(|c| c == 0),
+            _ => false,
+        }
+    }
+
+    /// Whether execution can continue at the next instruction.
+    pub fn falls_through(&self) -> bool {
+        match self.opcode {
+            Opcode::Halt => false,
+            Opcode::JumpIfTrue => self.condition().is_none_or(|c| c == 0),
+            Opcode::JumpIfFalse => self.condition().is_none_or(|c| c != 0),
+            _ => true,
+        }
+    }
+
+    /// Whether the instruction is a jump that is always taken, like `jt #1, ..`
+    /// or `jf #0, ..`.
+    pub fn is_unconditional_jump(&self) -> bool {
+        self.may_jump() && !self.falls_through()
+    }
+
+    /// Addresses execution can continue at, as far as can be known statically.
+    pub fn successors(&self) -> Vec<usize> {
+        let mut successors = Vec::new();
+        if self.falls_through() {
+            successors.push(self.next_address());
+        }
+
+        if self.may_jump() {
+            successors.extend(self.jump_target());
+        }
+
+        successors
+    }
+
+    /// The value written by an `add` or `mul` of two immediate values. This is
+    /// how return addresses are pushed on the stack.
+    pub fn constant_result(&self) -> Option<Value> {
+        match self.opcode {
+            Opcode::Add | Opcode::Multiply => {
+                let a = self.operands[0].immediate()?;
+                let b = self.operands[1].immediate()?;
+                if self.opcode == Opcode::Add {
+                    a.checked_add(b)
+                } else {
+                    a.checked_mul(b)
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The address written and the constant added by an `add` of an
+    /// immediate value and a position or relative one, which is how an
+    /// entry of a table is addressed.
+    pub fn offset_write(&self) -> Option<(usize, usize)> {
+        if self.opcode != Opcode::Add {
+            return None;
+        }
+
+        let target = &self.operands[2];
+        if target.mode != ParameterMode::Position || target.value.is_negative() {
+            return None;
+        }
+
+        let offset = match (self.operands[0].immediate(), self.operands[1].immediate()) {
+            (Some(offset), None) | (None, Some(offset)) => offset,
+            _ => return None,
+        };
+        Some((target.value as usize, usize::try_from(offset).ok()?))
+    }
+
+    fn condition(&self) -> Option<Value> {
+        self.operands[0].immediate()
+    }
+}
+
+impl fmt::Display for DecodedInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.opcode.mnemonic())?;
+        for (idx, operand) in self.operands.iter().enumerate() {
+            write!(f, "{}{}", if idx == 0 { " " } else { ", " }, operand)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Decode the instruction at `address`, if it is a valid one. Encodings the
+/// assembler would not produce are rejected, so that listings assemble back to
+/// the same program: modes for parameters the opcode does not have, and
+/// immediate mode for the address written to.
+pub fn decode(program: &[Value], address: usize) -> Option<DecodedInstruction> {
+    let raw = *program.get(address)?;
+    let Instruction { opcode, parameters } = Instruction::try_from(raw).ok()?;
+    let modes = [parameters.0, parameters.1, parameters.2];
+    let count = opcode.parameter_count();
+    if modes[count..]
+        .iter()
+        .any(|&mode| mode != ParameterMode::Position)
+    {
+        return None;
+    }
+
+    if opcode.writes_memory() && modes[count - 1] == ParameterMode::Immediate {
+        return None;
+    }
+    let operands = (0..count)
+        .map(|idx| {
+            program.get(address + 1 + idx).map(|&value| Operand {
+                mode: modes[idx],
+                value,
+            })
+        })
+        .collect::<Option<Vec<Operand>>>()?;
+
+    Some(DecodedInstruction {
+        address,
+        opcode,
+        operands,
+    })
+}
+
+/// Find the instructions reachable from address 0.
+///
+/// Jumps to immediate addresses are followed. Indirect jumps (used to return
+/// from functions) cannot be, so the address following an unconditional jump
+/// is also explored if it was pushed as a constant somewhere, which is how the
+/// return address of a call is set up.
+///
+/// Jump tables are followed too, see `table_jumps`.
+pub fn reachable(program: &[Value]) -> BTreeMap<usize, DecodedInstruction> {
+    reachable_from(program, &[0])
+}
+
+/// Same as `reachable`, from several entry points: for instance the targets of
+/// a jump table, which indirect jumps hide.
+pub fn reachable_from(program: &[Value], entries: &[usize]) -> BTreeMap<usize, DecodedInstruction> {
+    let mut code = BTreeMap::new();
+    let mut visited = HashSet::new();
+    let mut constants = HashSet::new();
+    let mut return_sites = HashSet::new();
+    let mut queue = entries.to_vec();
+
+    while !queue.is_empty() {
+        while let Some(address) = queue.pop() {
+            if !visited.insert(address) {
+                continue;
+            }
+
+            let instruction = match decode(program, address) {
+                Some(instruction) => instruction,
+                None => continue,
+            };
+
+            queue.extend(instruction.successors());
+            constants.extend(instruction.constant_result());
+            if instruction.is_unconditional_jump() {
+                return_sites.insert(instruction.next_address());
+            }
+
+            code.insert(address, instruction);
+        }
+
+        for table in table_jumps(&code).into_values() {
+            queue.extend(
+                jump_table(program, table)
+                    .into_iter()
+                    .filter(|address| !visited.contains(address)),
+            );
+        }
+
+        queue.extend(
+            return_sites
+                .iter()
+                .filter(|&&address| {
+                    constants.contains(&(address as Value)) && !visited.contains(&address)
+                })
+                .copied(),
+        );
+    }
+
+    code
+}
+
+/// Jumps reading their target from a jump table, by address, with the
+/// address of the table.
+///
+/// Programs dispatching on a value patch the operand of a jump with the
+/// address of an entry, for instance `add [x], #table, [jump+2]` followed by
+/// `jump: jt #1, [0]`: the jump goes to `mem[x + table]`.
+pub fn table_jumps(code: &BTreeMap<usize, DecodedInstruction>) -> BTreeMap<usize, usize> {
+    let offsets: HashMap<usize, usize> = code
+        .values()
+        .filter_map(DecodedInstruction::offset_write)
+        .collect();
+    code.values()
+        .filter(|instruction| {
+            instruction.may_jump()
+                && instruction.jump_target().is_none()
+                && instruction.operands[1].mode == ParameterMode::Position
+        })
+        .filter_map(|jump| Some((jump.address, *offsets.get(&(jump.address + 2))?)))
+        .collect()
+}
+
+/// Targets of the jump table starting at `table`, see `table_jumps`. Its size
+/// is unknown: it is taken to be the following values which are addresses of
+/// instructions after them.
+pub fn jump_table(program: &[Value], table: usize) -> Vec<usize> {
+    program
+        .iter()
+        .enumerate()
+        .skip(table)
+        .map_while(|(address, &target)| {
+            Some(target as usize)
+                .filter(|_| target > address as Value)
+                .filter(|&target| decode(program, target).is_some())
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    Instruction(DecodedInstruction),
+    Data { address: usize, values: Vec<Value> },
+}
+
+impl Line {
+    pub fn address(&self) -> usize {
+        match self {
+            Line::Instruction(instruction) => instruction.address,
+            Line::Data { address, .. } => *address,
+        }
+    }
+}
+
+/// Annotated listing of a program, with the raw values of every instruction.
+#[derive(Debug, Clone)]
+pub struct Listing {
+    pub lines: Vec<Line>,
+    program: Vec<Value>,
+}
+
+impl Listing {
+    pub fn raw(&self, line: &Line) -> &[Value] {
+        match line {
+            Line::Instruction(instruction) => {
+                &self.program[instruction.address..instruction.next_address()]
+            }
+            Line::Data { address, values } => &self.program[*address..*address + values.len()],
+        }
+    }
+
+    /// The text of a line of the listing, without a newline.
+    pub fn format_line(&self, line: &Line) -> String {
+        match line {
+            Line::Instruction(instruction) => {
+                let raw: Vec<String> = self.raw(line).iter().map(Value::to_string).collect();
+                let text = instruction.to_string();
+                format!(
+                    "{:>5}: {:<40} ; {}",
+                    instruction.address,
+                    text,
+                    raw.join(",")
+                )
+            }
+            Line::Data { address, values } => {
+                let values: Vec<String> = values.iter().map(Value::to_string).collect();
+                format!("{:>5}: data {}", address, values.join(", "))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", self.format_line(line))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Disassemble a program. Unreachable memory is listed as data.
+pub fn disassemble(program: &[Value]) -> Listing {
+    disassemble_from(program, &[0])
+}
+
+/// Same as `disassemble`, with the code reachable from several entry points,
+/// see `reachable_from`.
+pub fn disassemble_from(program: &[Value], entries: &[usize]) -> Listing {
+    let code = reachable_from(program, entries);
+    let mut lines = Vec::new();
+    let mut address = 0;
+
+    while address < program.len() {
+        if let Some(instruction) = code.get(&address) {
+            address = instruction.next_address();
+            lines.push(Line::Instruction(instruction.clone()));
+        } else {
+            let end = code
+                .range(address..)
+                .next()
+                .map_or(program.len(), |(&next, _)| next)
+                .min(address + DATA_PER_LINE);
+            lines.push(Line::Data {
+                address,
+                values: program[address..end].to_vec(),
+            });
+            address = end;
+        }
+    }
+
+    Listing {
+        lines,
+        program: program.to_vec(),
+    }
+}
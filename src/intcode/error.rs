@@ -0,0 +1,172 @@
+use super::{Address, Memory, Value, Word};
+use std::fmt;
+
+/// Number of memory values shown on each side of the faulting instruction.
+const WINDOW: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntCodeErrorKind {
+    InvalidAddress(Address),
+    InvalidOpCode(Value),
+    InvalidParameterMode(Value),
+    /// The input device cannot provide a value, for the given reason.
+    ReadError(&'static str),
+    /// The output device cannot accept a value, for the given reason.
+    WriteError(&'static str),
+    WriteImmediateMode,
+    /// The computer executed the given number of instructions without
+    /// halting.
+    InstructionLimit(u64),
+    /// The computer was still running when its deadline passed.
+    DeadlineExceeded,
+    /// The result of an instruction does not fit in a word, or the relative
+    /// base does not fit in an `Address`.
+    Overflow,
+    /// `Computer::rewind_to` cannot go back to the given instruction count.
+    NotInHistory(u64),
+}
+
+impl fmt::Display for IntCodeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntCodeErrorKind::InvalidAddress(address) => write!(f, "invalid address {}", address),
+            IntCodeErrorKind::InvalidOpCode(opcode) => write!(f, "invalid opcode {}", opcode),
+            IntCodeErrorKind::InvalidParameterMode(mode) => {
+                write!(f, "invalid parameter mode {}", mode)
+            }
+            IntCodeErrorKind::ReadError(reason) => write!(f, "cannot read input: {}", reason),
+            IntCodeErrorKind::WriteError(reason) => write!(f, "cannot write output: {}", reason),
+            IntCodeErrorKind::WriteImmediateMode => {
+                write!(f, "cannot write to an immediate mode parameter")
+            }
+            IntCodeErrorKind::InstructionLimit(limit) => {
+                write!(f, "instruction limit of {} reached", limit)
+            }
+            IntCodeErrorKind::DeadlineExceeded => write!(f, "deadline exceeded"),
+            IntCodeErrorKind::Overflow => write!(f, "arithmetic overflow"),
+            IntCodeErrorKind::NotInHistory(count) => {
+                write!(f, "instruction {} is not in the history", count)
+            }
+        }
+    }
+}
+
+/// State of the computer when an instruction failed. Words too large for a
+/// `Value` are clamped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorContext {
+    pub instruction_pointer: Address,
+    /// Raw value of the faulting instruction.
+    pub instruction: Value,
+    pub relative_base: isize,
+    /// Address of the first value of `memory`.
+    pub window_start: Address,
+    /// Memory around the faulting instruction.
+    pub memory: Vec<Value>,
+    /// Number of instructions executed since the program was loaded.
+    pub instruction_count: u64,
+}
+
+impl ErrorContext {
+    pub(super) fn new<W: Word>(
+        memory: &Memory<W>,
+        instruction_pointer: Address,
+        relative_base: isize,
+        instruction_count: u64,
+    ) -> Self {
+        let (window_start, window) = if instruction_pointer.is_negative() {
+            (instruction_pointer, Vec::new())
+        } else {
+            let ip = instruction_pointer as usize;
+            let start = ip.saturating_sub(WINDOW);
+            let end = (ip + WINDOW + 1).min(memory.len().max(ip + 1));
+            (
+                start as Address,
+                (start..end)
+                    .map(|address| memory.get(address).saturating_value())
+                    .collect(),
+            )
+        };
+
+        ErrorContext {
+            instruction_pointer,
+            instruction: if instruction_pointer.is_negative() {
+                0
+            } else {
+                memory.get(instruction_pointer as usize).saturating_value()
+            },
+            relative_base,
+            window_start,
+            memory: window,
+            instruction_count,
+        }
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "at address {} (instruction {}, relative base {}, after {} instructions); memory from {}:",
+            self.instruction_pointer,
+            self.instruction,
+            self.relative_base,
+            self.instruction_count,
+            self.window_start
+        )?;
+
+        for (offset, value) in self.memory.iter().enumerate() {
+            if self.window_start + offset as Address == self.instruction_pointer {
+                write!(f, " >{}<", value)?;
+            } else {
+                write!(f, " {}", value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntCodeError {
+    kind: IntCodeErrorKind,
+    context: Option<Box<ErrorContext>>,
+}
+
+impl IntCodeError {
+    pub fn kind(&self) -> IntCodeErrorKind {
+        self.kind
+    }
+
+    /// Where the error happened, if it was raised while executing a program.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        self.context.as_deref()
+    }
+
+    pub(super) fn with_context(mut self, context: ErrorContext) -> Self {
+        self.context = Some(Box::new(context));
+        self
+    }
+}
+
+impl From<IntCodeErrorKind> for IntCodeError {
+    fn from(kind: IntCodeErrorKind) -> Self {
+        IntCodeError {
+            kind,
+            context: None,
+        }
+    }
+}
+
+impl fmt::Display for IntCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(context) = &self.context {
+            write!(f, " {}", context)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for IntCodeError {}
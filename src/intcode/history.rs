@@ -0,0 +1,114 @@
+use super::{Address, MemoryWrite, Value};
+use std::collections::VecDeque;
+
+/// An executed instruction, with the registers before it was executed and
+/// what it changed, to undo it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry<W = Value> {
+    /// Number of instructions executed before this one.
+    pub instruction_count: u64,
+    pub instruction_pointer: Address,
+    pub relative_base: isize,
+    pub write: Option<MemoryWrite<W>>,
+    /// Value read by an `Input` instruction.
+    pub input: Option<W>,
+}
+
+/// Undo log of the instructions executed.
+#[derive(Debug, Clone)]
+pub(super) struct History<W> {
+    enabled: bool,
+    entries: Vec<HistoryEntry<W>>,
+    /// Instruction being executed.
+    current: Option<HistoryEntry<W>>,
+    /// Input read by undone instructions, to read again before the input
+    /// device.
+    replay: VecDeque<W>,
+}
+
+impl<W> Default for History<W> {
+    fn default() -> Self {
+        History {
+            enabled: false,
+            entries: Vec::new(),
+            current: None,
+            replay: VecDeque::new(),
+        }
+    }
+}
+
+impl<W> History<W> {
+    pub(super) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.clear();
+    }
+
+    pub(super) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(super) fn entries(&self) -> &[HistoryEntry<W>] {
+        &self.entries
+    }
+
+    /// Instruction count of the oldest instruction that can be undone.
+    pub(super) fn start(&self) -> Option<u64> {
+        self.entries.first().map(|entry| entry.instruction_count)
+    }
+
+    pub(super) fn begin(&mut self, instruction_pointer: Address, relative_base: isize) {
+        if self.enabled {
+            self.current = Some(HistoryEntry {
+                instruction_count: 0,
+                instruction_pointer,
+                relative_base,
+                write: None,
+                input: None,
+            });
+        }
+    }
+
+    pub(super) fn write(&mut self, write: MemoryWrite<W>) {
+        if let Some(current) = &mut self.current {
+            current.write = Some(write);
+        }
+    }
+
+    pub(super) fn input(&mut self, value: W) {
+        if let Some(current) = &mut self.current {
+            current.input = Some(value);
+        }
+    }
+
+    /// Keep the instruction being executed, which completed.
+    pub(super) fn commit(&mut self, instruction_count: u64) {
+        if let Some(mut current) = self.current.take() {
+            current.instruction_count = instruction_count;
+            self.entries.push(current);
+        }
+    }
+
+    /// Remove the last instruction executed, keeping its input to read again.
+    pub(super) fn undo(&mut self) -> Option<HistoryEntry<W>>
+    where
+        W: Clone,
+    {
+        let entry = self.entries.pop()?;
+        if let Some(value) = &entry.input {
+            self.replay.push_front(value.clone());
+        }
+
+        Some(entry)
+    }
+
+    /// Next input read by an undone instruction.
+    pub(super) fn replay(&mut self) -> Option<W> {
+        self.replay.pop_front()
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.entries.clear();
+        self.current = None;
+        self.replay.clear();
+    }
+}
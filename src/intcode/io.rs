@@ -0,0 +1,123 @@
+use super::{IntCodeErrorKind, IntCodeResult, Value};
+use std::collections::VecDeque;
+use std::sync::mpsc;
+
+/// Source of the values read by `Input` instructions.
+pub trait Input<W = Value> {
+    /// Next value, or `None` if there is none available yet.
+    fn try_read(&mut self) -> IntCodeResult<Option<W>>;
+
+    /// Next value, waiting for it if the source can still produce one.
+    fn read(&mut self) -> IntCodeResult<W> {
+        self.try_read()?
+            .ok_or_else(|| IntCodeErrorKind::ReadError("no input available").into())
+    }
+}
+
+/// Destination of the values written by `Output` instructions.
+pub trait Output<W = Value> {
+    fn write(&mut self, value: W) -> IntCodeResult<()>;
+}
+
+/// Feed the computer from an iterator.
+#[derive(Debug, Clone)]
+pub struct InputIter<I>(pub I);
+
+/// Feed the computer from a callback, which returns `None` if no value is
+/// available yet.
+#[derive(Debug, Clone)]
+pub struct InputFn<F>(pub F);
+
+/// Pass every output to a callback.
+#[derive(Debug, Clone)]
+pub struct OutputFn<F>(pub F);
+
+impl<W> Input<W> for mpsc::Receiver<W> {
+    fn try_read(&mut self) -> IntCodeResult<Option<W>> {
+        match self.try_recv() {
+            Ok(value) => Ok(Some(value)),
+            Err(mpsc::TryRecvError::Empty) => Ok(None),
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Err(IntCodeErrorKind::ReadError("input channel disconnected").into())
+            }
+        }
+    }
+
+    fn read(&mut self) -> IntCodeResult<W> {
+        self.recv()
+            .map_err(|_| IntCodeErrorKind::ReadError("input channel disconnected").into())
+    }
+}
+
+impl<W> Input<W> for VecDeque<W> {
+    fn try_read(&mut self) -> IntCodeResult<Option<W>> {
+        Ok(self.pop_front())
+    }
+
+    fn read(&mut self) -> IntCodeResult<W> {
+        self.pop_front()
+            .ok_or_else(|| IntCodeErrorKind::ReadError("input queue is empty").into())
+    }
+}
+
+impl<W, I: Iterator<Item = W>> Input<W> for InputIter<I> {
+    fn try_read(&mut self) -> IntCodeResult<Option<W>> {
+        Ok(self.0.next())
+    }
+
+    fn read(&mut self) -> IntCodeResult<W> {
+        self.0
+            .next()
+            .ok_or_else(|| IntCodeErrorKind::ReadError("input iterator is exhausted").into())
+    }
+}
+
+impl<W, F: FnMut() -> Option<W>> Input<W> for InputFn<F> {
+    fn try_read(&mut self) -> IntCodeResult<Option<W>> {
+        Ok((self.0)())
+    }
+}
+
+impl<W, T: Input<W> + ?Sized> Input<W> for &mut T {
+    fn try_read(&mut self) -> IntCodeResult<Option<W>> {
+        (**self).try_read()
+    }
+
+    fn read(&mut self) -> IntCodeResult<W> {
+        (**self).read()
+    }
+}
+
+impl<W> Output<W> for mpsc::Sender<W> {
+    fn write(&mut self, value: W) -> IntCodeResult<()> {
+        self.send(value)
+            .map_err(|_| IntCodeErrorKind::WriteError("output channel disconnected").into())
+    }
+}
+
+impl<W> Output<W> for Vec<W> {
+    fn write(&mut self, value: W) -> IntCodeResult<()> {
+        self.push(value);
+        Ok(())
+    }
+}
+
+impl<W> Output<W> for VecDeque<W> {
+    fn write(&mut self, value: W) -> IntCodeResult<()> {
+        self.push_back(value);
+        Ok(())
+    }
+}
+
+impl<W, F: FnMut(W)> Output<W> for OutputFn<F> {
+    fn write(&mut self, value: W) -> IntCodeResult<()> {
+        (self.0)(value);
+        Ok(())
+    }
+}
+
+impl<W, T: Output<W> + ?Sized> Output<W> for &mut T {
+    fn write(&mut self, value: W) -> IntCodeResult<()> {
+        (**self).write(value)
+    }
+}
@@ -0,0 +1,137 @@
+use super::{Address, IntCodeErrorKind, IntCodeResult, Value, Word};
+use std::collections::BTreeMap;
+
+/// Addresses below this are stored in a single vector, grown as needed.
+const DENSE_LIMIT: usize = 1 << 16;
+/// Number of values in each page of sparse memory.
+pub const PAGE_SIZE: usize = 1 << 10;
+
+/// Memory of a `Computer`.
+///
+/// The program and the addresses following it are stored in a vector, so
+/// dense programs are as fast as with a plain `Vec`. Addresses further away
+/// (from `DENSE_LIMIT`, or from the end of the program if it is longer) are
+/// stored in pages, allocated on the first write. Unwritten addresses read
+/// as 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Memory<W = Value> {
+    dense: Vec<W>,
+    pages: BTreeMap<usize, Box<[W]>>,
+    max_address: Option<usize>,
+}
+
+impl<W> Default for Memory<W> {
+    fn default() -> Self {
+        Memory {
+            dense: Vec::new(),
+            pages: BTreeMap::new(),
+            max_address: None,
+        }
+    }
+}
+
+impl<W: Word> Memory<W> {
+    pub fn new() -> Self {
+        Memory::default()
+    }
+
+    /// Replace the content of memory by a program, keeping the maximum
+    /// address.
+    pub fn load(&mut self, program: Vec<W>) {
+        self.dense = program;
+        self.pages.clear();
+    }
+
+    /// Highest address the program may use past its own end, if any. Reading
+    /// or writing a higher one fails with `IntCodeErrorKind::InvalidAddress`
+    /// instead of allocating memory.
+    pub fn max_address(&self) -> Option<usize> {
+        self.max_address
+    }
+
+    pub fn set_max_address(&mut self, max_address: Option<usize>) {
+        self.max_address = max_address;
+    }
+
+    pub fn get(&self, address: usize) -> W {
+        match self.dense.get(address) {
+            Some(value) => value.clone(),
+            None => self.pages.get(&(address / PAGE_SIZE)).map_or_else(
+                || W::from_value(0),
+                |page| page[address % PAGE_SIZE].clone(),
+            ),
+        }
+    }
+
+    /// Values from address 0, up to the end of the dense part of memory.
+    pub fn as_slice(&self) -> &[W] {
+        &self.dense
+    }
+
+    /// Allocated pages of sparse memory, with the address of their first value,
+    /// in increasing order.
+    pub fn pages(&self) -> impl Iterator<Item = (usize, &[W])> + '_ {
+        self.pages
+            .iter()
+            .map(|(&page, values)| (page * PAGE_SIZE, &values[..]))
+    }
+
+    /// Number of values from address 0 to the highest allocated one.
+    pub fn len(&self) -> usize {
+        self.pages
+            .keys()
+            .next_back()
+            .map_or(0, |&page| (page + 1) * PAGE_SIZE)
+            .max(self.dense.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(super) fn read(&self, address: Address) -> IntCodeResult<W> {
+        // Negative addresses wrap around and miss the vector as well.
+        match self.dense.get(address as usize) {
+            Some(value) => Ok(value.clone()),
+            None => self.check(address).map(|address| self.get(address)),
+        }
+    }
+
+    pub(super) fn write(&mut self, address: Address, value: W) -> IntCodeResult<()> {
+        if let Some(cell) = self.dense.get_mut(address as usize) {
+            *cell = value;
+            return Ok(());
+        }
+
+        let address = self.check(address)?;
+        if address < DENSE_LIMIT {
+            self.dense.resize(address + 1, W::from_value(0));
+            self.dense[address] = value;
+        } else {
+            let page = self
+                .pages
+                .entry(address / PAGE_SIZE)
+                .or_insert_with(|| vec![W::from_value(0); PAGE_SIZE].into_boxed_slice());
+            page[address % PAGE_SIZE] = value;
+        }
+
+        Ok(())
+    }
+
+    fn check(&self, address: Address) -> IntCodeResult<usize> {
+        if address.is_negative() || self.max_address.is_some_and(|max| address as usize > max) {
+            Err(IntCodeErrorKind::InvalidAddress(address).into())
+        } else {
+            Ok(address as usize)
+        }
+    }
+}
+
+impl<W> From<Vec<W>> for Memory<W> {
+    fn from(program: Vec<W>) -> Self {
+        Memory {
+            dense: program,
+            ..Memory::default()
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use super::{Address, Computer, IntCodeResult, Program, Status, Value};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Address of the NAT on a `Network`.
+pub const NAT_ADDRESS: Address = 255;
+
+/// A packet sent on a `Network`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet {
+    /// Round of the network in which the packet was sent.
+    pub round: u64,
+    pub source: Address,
+    pub destination: Address,
+    pub x: Value,
+    pub y: Value,
+}
+
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>6}: {:>3} -> {:>3} ({}, {})",
+            self.round, self.source, self.destination, self.x, self.y
+        )
+    }
+}
+
+/// Receiver of the packets sent to `NAT_ADDRESS`, which may send a packet
+/// when the network is idle.
+pub trait Nat {
+    fn receive(&mut self, packet: Packet);
+
+    /// Packet to send when the network is idle, in the given round.
+    fn wake(&mut self, round: u64) -> Option<Packet>;
+}
+
+/// The NAT of day 23: it keeps the last packet received, and sends it to
+/// address 0 when the network is idle.
+#[derive(Debug, Clone, Default)]
+pub struct LastPacketNat {
+    last: Option<Packet>,
+}
+
+impl Nat for LastPacketNat {
+    fn receive(&mut self, packet: Packet) {
+        self.last = Some(packet);
+    }
+
+    fn wake(&mut self, round: u64) -> Option<Packet> {
+        self.last.map(|packet| Packet {
+            round,
+            source: NAT_ADDRESS,
+            destination: 0,
+            ..packet
+        })
+    }
+}
+
+/// Computers running the same program, each given its address as first input
+/// and exchanging packets: three outputs, the address then X and Y. Computers
+/// waiting for a packet read -1.
+///
+/// The network runs in rounds, each computer in turn running until it waits
+/// for input, so runs do not depend on thread scheduling. Packets are
+/// delivered as soon as they are sent, and a round without any packet sent
+/// leaves the network idle.
+pub struct Network {
+    computers: Vec<Computer<VecDeque<Value>, Vec<Value>>>,
+    nat: Option<Box<dyn Nat + Send>>,
+    packets: Vec<Packet>,
+    round: u64,
+}
+
+impl Network {
+    pub fn new(program: &Program, size: usize) -> Self {
+        let computers = (0..size)
+            .map(|address| {
+                let mut computer = Computer::with_io(VecDeque::new(), Vec::new());
+                computer.input_device_mut().push_back(address as Value);
+                computer.load_program(program.clone());
+                computer
+            })
+            .collect();
+
+        Network {
+            computers,
+            nat: None,
+            packets: Vec::new(),
+            round: 0,
+        }
+    }
+
+    /// Handle the packets sent to `NAT_ADDRESS`. Without a NAT, they are only
+    /// logged.
+    pub fn set_nat<N: Nat + Send + 'static>(&mut self, nat: N) -> &mut Self {
+        self.nat = Some(Box::new(nat));
+        self
+    }
+
+    pub fn computers(&self) -> &[Computer<VecDeque<Value>, Vec<Value>>] {
+        &self.computers
+    }
+
+    /// Every packet sent so far, in order, including the ones sent by the NAT.
+    pub fn packets(&self) -> &[Packet] {
+        &self.packets
+    }
+
+    /// Number of rounds run.
+    pub fn rounds(&self) -> u64 {
+        self.round
+    }
+
+    /// Run every computer until it waits for input, returning whether the
+    /// network was idle. The NAT is then woken up, if any.
+    pub fn run_round(&mut self) -> IntCodeResult<bool> {
+        let sent = self.packets.len();
+        for source in 0..self.computers.len() {
+            self.run_computer(source)?;
+        }
+
+        let idle = self.packets.len() == sent;
+        if idle {
+            let round = self.round;
+            if let Some(packet) = self.nat.as_mut().and_then(|nat| nat.wake(round)) {
+                self.send(packet);
+            }
+        }
+
+        self.round += 1;
+        Ok(idle)
+    }
+
+    /// Run rounds until `stop` returns true after one of them, given the log
+    /// of packets and whether the network was idle.
+    pub fn run_until<F>(&mut self, mut stop: F) -> IntCodeResult<()>
+    where
+        F: FnMut(&[Packet], bool) -> bool,
+    {
+        loop {
+            let idle = self.run_round()?;
+            if stop(&self.packets, idle) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn run_computer(&mut self, source: usize) -> IntCodeResult<()> {
+        loop {
+            let computer = &mut self.computers[source];
+            match computer.run()? {
+                Status::Output(value) => {
+                    let output = computer.output_device_mut();
+                    output.push(value);
+                    if let [destination, x, y] = output[..] {
+                        output.clear();
+                        self.send(Packet {
+                            round: self.round,
+                            source: source as Address,
+                            destination,
+                            x,
+                            y,
+                        });
+                    }
+                }
+                Status::NeedsInput => {
+                    computer.input_device_mut().push_back(-1);
+                    return Ok(());
+                }
+                Status::Halted => return Ok(()),
+                Status::Running => unreachable!(),
+            }
+        }
+    }
+
+    /// Log and deliver a packet. Packets to unknown addresses are dropped.
+    fn send(&mut self, packet: Packet) {
+        self.packets.push(packet);
+        if packet.destination == NAT_ADDRESS {
+            if let Some(nat) = &mut self.nat {
+                nat.receive(packet);
+            }
+        } else if let Some(computer) = usize::try_from(packet.destination)
+            .ok()
+            .and_then(|destination| self.computers.get_mut(destination))
+        {
+            computer.input_device_mut().extend(&[packet.x, packet.y]);
+        }
+    }
+}
+
+impl fmt::Debug for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Network")
+            .field("computers", &self.computers)
+            .field("packets", &self.packets)
+            .field("round", &self.round)
+            .finish_non_exhaustive()
+    }
+}
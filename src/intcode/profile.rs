@@ -0,0 +1,266 @@
+use super::decompile::function_name;
+use super::disasm;
+use super::{Address, Opcode, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+/// How often a conditional jump was taken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BranchCount {
+    pub taken: u64,
+    pub not_taken: u64,
+    /// Where the jump went the last time it was taken.
+    pub target: Option<Address>,
+}
+
+/// Execution counts of the instructions run by a `Computer`, by address, by
+/// opcode and by call stack.
+///
+/// Calls are jumps made right after the address following them was pushed at
+/// the relative base, and returns are jumps to a relative-mode address.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    counts: BTreeMap<Address, u64>,
+    opcodes: [u64; Opcode::ALL.len()],
+    branches: BTreeMap<Address, BranchCount>,
+    /// Functions being executed, by entry address, with their return address.
+    frames: Vec<(Address, Address)>,
+    /// Index of the current call stack in `stacks`.
+    stack: usize,
+    stacks: Vec<(Vec<Address>, u64)>,
+    stack_ids: HashMap<Vec<Address>, usize>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        let mut stack_ids = HashMap::new();
+        stack_ids.insert(Vec::new(), 0);
+        Profile {
+            counts: BTreeMap::new(),
+            opcodes: [0; Opcode::ALL.len()],
+            branches: BTreeMap::new(),
+            frames: Vec::new(),
+            stack: 0,
+            stacks: vec![(Vec::new(), 0)],
+            stack_ids,
+        }
+    }
+}
+
+impl Profile {
+    /// Number of instructions executed.
+    pub fn instruction_count(&self) -> u64 {
+        self.opcodes.iter().sum()
+    }
+
+    /// Executions of the instruction at `address`.
+    pub fn count(&self, address: Address) -> u64 {
+        self.counts.get(&address).copied().unwrap_or(0)
+    }
+
+    /// Addresses of the instructions executed, with their counts.
+    pub fn counts(&self) -> impl Iterator<Item = (Address, u64)> + '_ {
+        self.counts
+            .iter()
+            .map(|(&address, &count)| (address, count))
+    }
+
+    pub fn opcode_count(&self, opcode: Opcode) -> u64 {
+        self.opcodes[opcode_index(opcode)]
+    }
+
+    /// Statistics of every `jt` and `jf` executed, by address.
+    pub fn branches(&self) -> &BTreeMap<Address, BranchCount> {
+        &self.branches
+    }
+
+    /// Instructions executed by call stack, in the folded format read by
+    /// flame graph tools: one `main;func_436;func_1889 1234` line per stack.
+    pub fn to_folded(&self) -> String {
+        let mut lines: Vec<String> = self
+            .stacks
+            .iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(stack, count)| {
+                let mut names = vec!["main".to_string()];
+                names.extend(stack.iter().map(|&entry| function_name(entry as usize)));
+                format!("{} {}", names.join(";"), count)
+            })
+            .collect();
+        lines.sort();
+
+        let mut folded = lines.join("\n");
+        folded.push('\n');
+        folded
+    }
+
+    /// Hot spots, hot loops and branches, `top` of each, most executed first.
+    /// The program is used to show the instructions, and to find the loops:
+    /// backward jumps to a fixed address.
+    pub fn report(&self, program: &[Value], top: usize) -> String {
+        let mut report = String::new();
+        self.write_report(&mut report, program, top).unwrap();
+        report
+    }
+
+    fn write_report(&self, f: &mut String, program: &[Value], top: usize) -> std::fmt::Result {
+        let total = self.instruction_count();
+        let percent = |count: u64| 100.0 * count as f64 / total.max(1) as f64;
+        writeln!(f, "Profile of {} instructions", total)?;
+
+        writeln!(f, "\nOpcodes:")?;
+        let mut opcodes: Vec<(Opcode, u64)> = Opcode::ALL
+            .iter()
+            .map(|&opcode| (opcode, self.opcode_count(opcode)))
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        opcodes.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        for (opcode, count) in opcodes {
+            writeln!(
+                f,
+                "  {:<4} {:>12} {:>6.2}%",
+                opcode.mnemonic(),
+                count,
+                percent(count)
+            )?;
+        }
+
+        writeln!(f, "\nHot spots:")?;
+        writeln!(
+            f,
+            "  {:>7} {:>12} {:>7}  instruction",
+            "address", "count", "%"
+        )?;
+        let mut counts: Vec<(Address, u64)> = self.counts().collect();
+        counts.sort_by_key(|&(address, count)| (std::cmp::Reverse(count), address));
+        for &(address, count) in counts.iter().take(top) {
+            let instruction = disasm::decode(program, address as usize)
+                .map_or_else(|| "?".to_string(), |instruction| instruction.to_string());
+            writeln!(
+                f,
+                "  {:>7} {:>12} {:>6.2}%  {}",
+                address,
+                count,
+                percent(count),
+                instruction
+            )?;
+        }
+
+        writeln!(f, "\nHot loops:")?;
+        writeln!(
+            f,
+            "  {:>7} {:>7} {:>12} {:>12} {:>7}",
+            "from", "to", "iterations", "instructions", "%"
+        )?;
+        let mut loops: Vec<(Address, Address, u64, u64)> = self
+            .branches
+            .iter()
+            .filter_map(|(&address, branch)| {
+                // Returns jump backwards too, only fixed targets make loops.
+                let target = disasm::decode(program, address as usize)?.jump_target()? as Address;
+                if target > address || branch.taken == 0 {
+                    return None;
+                }
+
+                let instructions = self
+                    .counts
+                    .range(target..=address)
+                    .map(|(_, count)| count)
+                    .sum();
+                Some((target, address, branch.taken, instructions))
+            })
+            .collect();
+        loops.sort_by_key(|&(from, _, _, instructions)| (std::cmp::Reverse(instructions), from));
+        for &(from, to, iterations, instructions) in loops.iter().take(top) {
+            writeln!(
+                f,
+                "  {:>7} {:>7} {:>12} {:>12} {:>6.2}%",
+                from,
+                to,
+                iterations,
+                instructions,
+                percent(instructions)
+            )?;
+        }
+
+        writeln!(f, "\nBranches:")?;
+        writeln!(f, "  {:>7} {:>12} {:>12}", "address", "taken", "not taken")?;
+        let mut branches: Vec<(&Address, &BranchCount)> = self.branches.iter().collect();
+        branches.sort_by_key(|&(&address, branch)| {
+            (std::cmp::Reverse(branch.taken + branch.not_taken), address)
+        });
+        for (address, branch) in branches.into_iter().take(top) {
+            writeln!(
+                f,
+                "  {:>7} {:>12} {:>12}",
+                address, branch.taken, branch.not_taken
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn record(&mut self, address: Address, opcode: Opcode) {
+        *self.counts.entry(address).or_insert(0) += 1;
+        self.opcodes[opcode_index(opcode)] += 1;
+        self.stacks[self.stack].1 += 1;
+    }
+
+    pub(super) fn record_branch(&mut self, address: Address, taken: Option<Address>) {
+        let branch = self.branches.entry(address).or_default();
+        match taken {
+            Some(target) => {
+                branch.taken += 1;
+                branch.target = Some(target);
+            }
+            None => branch.not_taken += 1,
+        }
+    }
+
+    pub(super) fn call(&mut self, entry: Address, return_address: Address) {
+        self.frames.push((entry, return_address));
+        self.update_stack();
+    }
+
+    /// Return to `address`, from the innermost function returning there.
+    pub(super) fn return_to(&mut self, address: Address) {
+        if let Some(depth) = self
+            .frames
+            .iter()
+            .rposition(|&(_, return_address)| return_address == address)
+        {
+            self.frames.truncate(depth);
+            self.update_stack();
+        }
+    }
+
+    /// Forget the call stack, when starting the program again.
+    pub(super) fn restart(&mut self) {
+        self.frames.clear();
+        self.stack = 0;
+    }
+
+    fn update_stack(&mut self) {
+        let stack: Vec<Address> = self.frames.iter().map(|&(entry, _)| entry).collect();
+        let stacks = &mut self.stacks;
+        self.stack = *self.stack_ids.entry(stack).or_insert_with_key(|stack| {
+            stacks.push((stack.clone(), 0));
+            stacks.len() - 1
+        });
+    }
+}
+
+fn opcode_index(opcode: Opcode) -> usize {
+    match opcode {
+        Opcode::Add => 0,
+        Opcode::Multiply => 1,
+        Opcode::Input => 2,
+        Opcode::Output => 3,
+        Opcode::JumpIfTrue => 4,
+        Opcode::JumpIfFalse => 5,
+        Opcode::LessThan => 6,
+        Opcode::Equals => 7,
+        Opcode::RelativeBaseOffset => 8,
+        Opcode::Halt => 9,
+    }
+}
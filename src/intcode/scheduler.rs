@@ -0,0 +1,288 @@
+use super::{Computer, IntCodeResult, Program, Status, Value};
+use std::collections::VecDeque;
+use std::fmt;
+
+type Machine = Computer<VecDeque<Value>, Vec<Value>>;
+
+/// Chooses which machine a `Scheduler` runs next.
+pub trait Policy {
+    /// One of the `runnable` machines, given in increasing order and never
+    /// empty.
+    fn next(&mut self, runnable: &[usize]) -> usize;
+}
+
+/// Run the machines in turn, the default.
+#[derive(Debug, Clone, Default)]
+pub struct RoundRobin {
+    last: Option<usize>,
+}
+
+impl Policy for RoundRobin {
+    fn next(&mut self, runnable: &[usize]) -> usize {
+        let next = match self.last {
+            Some(last) => runnable
+                .iter()
+                .copied()
+                .find(|&id| id > last)
+                .unwrap_or(runnable[0]),
+            None => runnable[0],
+        };
+
+        self.last = Some(next);
+        next
+    }
+}
+
+/// Run the machines in a pseudo-random order, always the same for a seed.
+#[derive(Debug, Clone)]
+pub struct SeededRandom {
+    state: u64,
+}
+
+impl SeededRandom {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift never leaves 0.
+        SeededRandom { state: seed.max(1) }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+}
+
+impl Policy for SeededRandom {
+    fn next(&mut self, runnable: &[usize]) -> usize {
+        runnable[(self.next_u64() % runnable.len() as u64) as usize]
+    }
+}
+
+/// Why a machine stopped running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Switch {
+    Blocked,
+    Halted,
+    /// It ran for a whole time slice.
+    Preempted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleEvent {
+    /// A machine ran, after the values it sent.
+    Run {
+        machine: usize,
+        instructions: u64,
+        switch: Switch,
+    },
+    /// A machine output a value, sent to the machine it is connected to.
+    Send {
+        from: usize,
+        to: Option<usize>,
+        value: Value,
+    },
+}
+
+impl fmt::Display for ScheduleEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScheduleEvent::Run {
+                machine,
+                instructions,
+                switch,
+            } => write!(
+                f,
+                "run {} for {} instructions: {:?}",
+                machine, instructions, switch
+            ),
+            ScheduleEvent::Send {
+                from,
+                to: Some(to),
+                value,
+            } => write!(f, "send {} from {} to {}", value, from, to),
+            ScheduleEvent::Send {
+                from,
+                to: None,
+                value,
+            } => write!(f, "output {} from {}", value, from),
+        }
+    }
+}
+
+/// Runs machines on the current thread, switching when the one running waits
+/// for input or halts, or at the end of its time slice. The outputs of a
+/// machine go to the input of the one it is connected to, if any, or to its
+/// output device. Runs only depend on the policy, so they can be replayed.
+pub struct Scheduler {
+    machines: Vec<Machine>,
+    routes: Vec<Option<usize>>,
+    blocked: Vec<bool>,
+    halted: Vec<bool>,
+    policy: Box<dyn Policy + Send>,
+    time_slice: Option<u64>,
+    trace: Vec<ScheduleEvent>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler {
+            machines: Vec::new(),
+            routes: Vec::new(),
+            blocked: Vec::new(),
+            halted: Vec::new(),
+            policy: Box::new(RoundRobin::default()),
+            time_slice: None,
+            trace: Vec::new(),
+        }
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Scheduler::default()
+    }
+
+    pub fn set_policy<P: Policy + Send + 'static>(&mut self, policy: P) -> &mut Self {
+        self.policy = Box::new(policy);
+        self
+    }
+
+    /// Switch machines after this many instructions even if they could go on.
+    /// Unlimited by default.
+    pub fn set_time_slice(&mut self, time_slice: Option<u64>) -> &mut Self {
+        self.time_slice = time_slice;
+        self
+    }
+
+    /// Add a machine running the program, returning its id.
+    pub fn add(&mut self, program: Program) -> usize {
+        let mut machine = Computer::with_io(VecDeque::new(), Vec::new());
+        machine.load_program(program);
+        self.machines.push(machine);
+        self.routes.push(None);
+        self.blocked.push(false);
+        self.halted.push(false);
+        self.machines.len() - 1
+    }
+
+    /// Send the outputs of a machine to the input of another.
+    pub fn connect(&mut self, from: usize, to: usize) -> &mut Self {
+        self.routes[from] = Some(to);
+        self
+    }
+
+    /// Queue an input value for a machine.
+    pub fn send(&mut self, machine: usize, value: Value) -> &mut Self {
+        self.machines[machine].input_device_mut().push_back(value);
+        self.blocked[machine] = false;
+        self
+    }
+
+    pub fn machine(&self, id: usize) -> &Machine {
+        &self.machines[id]
+    }
+
+    pub fn machine_mut(&mut self, id: usize) -> &mut Machine {
+        &mut self.machines[id]
+    }
+
+    pub fn is_halted(&self, id: usize) -> bool {
+        self.halted[id]
+    }
+
+    /// Whether every machine halted, rather than waiting for input.
+    pub fn all_halted(&self) -> bool {
+        self.halted.iter().all(|&halted| halted)
+    }
+
+    /// Machines which are neither halted nor waiting for input.
+    pub fn runnable(&self) -> Vec<usize> {
+        (0..self.machines.len())
+            .filter(|&id| !self.halted[id] && !self.blocked[id])
+            .collect()
+    }
+
+    /// What happened so far.
+    pub fn trace(&self) -> &[ScheduleEvent] {
+        &self.trace
+    }
+
+    /// Run the machine chosen by the policy, returning false if none can run.
+    pub fn step(&mut self) -> IntCodeResult<bool> {
+        let runnable = self.runnable();
+        if runnable.is_empty() {
+            return Ok(false);
+        }
+
+        let id = self.policy.next(&runnable);
+        self.run_machine(id)?;
+        Ok(true)
+    }
+
+    /// Run until every machine halted or waits for input.
+    pub fn run(&mut self) -> IntCodeResult<()> {
+        while self.step()? {}
+        Ok(())
+    }
+
+    fn run_machine(&mut self, id: usize) -> IntCodeResult<()> {
+        let start = self.machines[id].instruction_count();
+        let switch = loop {
+            let instructions = self.machines[id].instruction_count() - start;
+            if self
+                .time_slice
+                .is_some_and(|time_slice| instructions >= time_slice)
+            {
+                break Switch::Preempted;
+            }
+
+            match self.machines[id].step()? {
+                Status::Running => (),
+                Status::Output(value) => {
+                    let to = self.routes[id];
+                    match to {
+                        Some(to) => {
+                            self.send(to, value);
+                        }
+                        None => self.machines[id].output_device_mut().push(value),
+                    }
+
+                    self.trace.push(ScheduleEvent::Send {
+                        from: id,
+                        to,
+                        value,
+                    });
+                }
+                Status::NeedsInput => {
+                    self.blocked[id] = true;
+                    break Switch::Blocked;
+                }
+                Status::Halted => {
+                    self.halted[id] = true;
+                    break Switch::Halted;
+                }
+            }
+        };
+
+        self.trace.push(ScheduleEvent::Run {
+            machine: id,
+            instructions: self.machines[id].instruction_count() - start,
+            switch,
+        });
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("machines", &self.machines)
+            .field("routes", &self.routes)
+            .field("blocked", &self.blocked)
+            .field("halted", &self.halted)
+            .field("time_slice", &self.time_slice)
+            .field("trace", &self.trace)
+            .finish_non_exhaustive()
+    }
+}
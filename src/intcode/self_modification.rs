@@ -0,0 +1,92 @@
+use super::{Address, Value};
+use std::collections::HashMap;
+
+/// A write by the program to an address previously executed as part of an
+/// instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeWrite<W = Value> {
+    /// Address of the instruction writing.
+    pub instruction_pointer: Address,
+    pub address: Address,
+    pub value: W,
+    /// Address of the instruction written to, the last one executed there.
+    pub instruction: Address,
+    /// Number of instructions executed before the write.
+    pub instruction_count: u64,
+}
+
+/// Which addresses were executed, to record the writes to them.
+#[derive(Debug, Clone)]
+pub(super) struct SelfModificationDetector<W> {
+    enabled: bool,
+    /// Start of the last instruction executed including each address. Memory
+    /// is sparse, so this is keyed by address rather than indexed.
+    executed: HashMap<Address, Address>,
+    /// Start of the instruction being executed.
+    current: Address,
+    writes: Vec<CodeWrite<W>>,
+}
+
+impl<W> Default for SelfModificationDetector<W> {
+    fn default() -> Self {
+        SelfModificationDetector {
+            enabled: false,
+            executed: HashMap::new(),
+            current: 0,
+            writes: Vec::new(),
+        }
+    }
+}
+
+impl<W> SelfModificationDetector<W> {
+    pub(super) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.clear();
+    }
+
+    pub(super) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Record the execution of the instruction at `address`, `width` values
+    /// long with its parameters.
+    pub(super) fn execute(&mut self, address: Address, width: usize) {
+        if !self.enabled {
+            return;
+        }
+
+        for offset in 0..width as Address {
+            self.executed.insert(address + offset, address);
+        }
+
+        self.current = address;
+    }
+
+    pub(super) fn write(&mut self, address: Address, value: &W, instruction_count: u64)
+    where
+        W: Clone,
+    {
+        if !self.enabled {
+            return;
+        }
+
+        if let Some(&instruction) = self.executed.get(&address) {
+            self.writes.push(CodeWrite {
+                instruction_pointer: self.current,
+                address,
+                value: value.clone(),
+                instruction,
+                instruction_count,
+            });
+        }
+    }
+
+    pub(super) fn writes(&self) -> &[CodeWrite<W>] {
+        &self.writes
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.executed.clear();
+        self.writes.clear();
+    }
+}
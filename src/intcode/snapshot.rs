@@ -0,0 +1,263 @@
+use super::{Address, Computer, Input, Memory, Output, Value, Word};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::iter::FromIterator;
+use std::path::Path;
+
+/// Full state of a `Computer`, including its input and output devices (for
+/// example, the values still waiting in a `VecDeque`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot<I = VecDeque<Value>, O = VecDeque<Value>, W = Value> {
+    pub memory: Memory<W>,
+    pub instruction_pointer: Address,
+    pub relative_base: isize,
+    /// Number of instructions executed, see `Computer::instruction_count`.
+    pub instruction_count: u64,
+    pub input: I,
+    pub output: O,
+}
+
+impl<I: Input<W> + Clone, O: Output<W> + Clone, W: Word> Snapshot<I, O, W> {
+    /// Create a new, independent computer in this state.
+    pub fn restore(&self) -> Computer<I, O, W> {
+        Computer::from(self.clone())
+    }
+}
+
+impl<I: Input<W>, O: Output<W>, W: Word> From<Snapshot<I, O, W>> for Computer<I, O, W> {
+    fn from(snapshot: Snapshot<I, O, W>) -> Self {
+        let mut computer = Computer::with_devices(snapshot.input, snapshot.output);
+        computer.memory = snapshot.memory;
+        computer.instruction_pointer = snapshot.instruction_pointer;
+        computer.relative_base = snapshot.relative_base;
+        computer.instruction_count = snapshot.instruction_count;
+        computer
+    }
+}
+
+impl<I: Input<W> + Clone, O: Output<W> + Clone, W: Word> Computer<I, O, W> {
+    pub fn snapshot(&self) -> Snapshot<I, O, W> {
+        Snapshot {
+            memory: self.memory.clone(),
+            instruction_pointer: self.instruction_pointer,
+            relative_base: self.relative_base,
+            instruction_count: self.instruction_count,
+            input: self.input.clone(),
+            output: self.output.clone(),
+        }
+    }
+
+    /// Return to the state of a snapshot. The maximum address is configuration
+    /// rather than state, so the computer keeps its own.
+    pub fn restore(&mut self, snapshot: &Snapshot<I, O, W>) -> &mut Self {
+        let max_address = self.memory.max_address();
+        self.memory.clone_from(&snapshot.memory);
+        self.memory.set_max_address(max_address);
+        self.cache.clear();
+        self.instruction_pointer = snapshot.instruction_pointer;
+        self.relative_base = snapshot.relative_base;
+        self.instruction_count = snapshot.instruction_count;
+        self.input = snapshot.input.clone();
+        self.output = snapshot.output.clone();
+        self.last_write = None;
+        self.history.clear();
+        self
+    }
+}
+
+/// Version written by `save_snapshot`.
+pub const SNAPSHOT_VERSION: u32 = 3;
+
+/// Oldest version `load_snapshot` can read. Version 1 had no sparse pages,
+/// and versions before 3 no instruction count.
+const OLDEST_VERSION: u32 = 1;
+
+const MAGIC: &str = "intcode-snapshot";
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// The file was written in a version of the format this one cannot read.
+    UnsupportedVersion(String),
+    /// The file is not a snapshot, or it is damaged.
+    InvalidFormat(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "{}", error),
+            SnapshotError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported snapshot version {} (expected {} to {})",
+                version, OLDEST_VERSION, SNAPSHOT_VERSION
+            ),
+            SnapshotError::InvalidFormat(reason) => write!(f, "invalid snapshot: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
+
+/// The snapshot format is text, with one field per line, in this order:
+///
+/// ```text
+/// intcode-snapshot 3
+/// instruction_pointer 42
+/// relative_base 1000
+/// instruction_count 123456
+/// input 1,2,3
+/// output
+/// memory 1101,0,3,1000,...
+/// page 1048576 0,0,7,...
+/// ```
+///
+/// The first line holds the format version. Lists are comma-separated and
+/// may be empty. `input` holds the values not read yet, and `output` those
+/// not consumed yet. The dense part of memory is stored from address 0,
+/// followed by one `page` line per page of sparse memory, with the address
+/// of its first value.
+impl<I, O, W> Snapshot<I, O, W>
+where
+    for<'a> &'a I: IntoIterator<Item = &'a W>,
+    for<'a> &'a O: IntoIterator<Item = &'a W>,
+    I: FromIterator<W>,
+    O: FromIterator<W>,
+    W: Word,
+{
+    pub fn write_to<T: Write>(&self, mut writer: T) -> io::Result<()> {
+        writeln!(writer, "{} {}", MAGIC, SNAPSHOT_VERSION)?;
+        writeln!(writer, "instruction_pointer {}", self.instruction_pointer)?;
+        writeln!(writer, "relative_base {}", self.relative_base)?;
+        writeln!(writer, "instruction_count {}", self.instruction_count)?;
+        writeln!(writer, "input {}", join(&self.input))?;
+        writeln!(writer, "output {}", join(&self.output))?;
+        writeln!(writer, "memory {}", join(self.memory.as_slice()))?;
+        for (address, values) in self.memory.pages() {
+            writeln!(writer, "page {} {}", address, join(values))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from<R: BufRead>(reader: R) -> Result<Self, SnapshotError> {
+        let mut lines = reader.lines();
+        let mut field = |name: &str| -> Result<String, SnapshotError> {
+            let line = lines
+                .next()
+                .ok_or_else(|| SnapshotError::InvalidFormat(format!("missing `{}`", name)))??;
+            match line.split_once(' ') {
+                Some((key, value)) if key == name => Ok(value.trim().to_string()),
+                None if line == name => Ok(String::new()),
+                _ => Err(SnapshotError::InvalidFormat(format!(
+                    "expected `{}`, found `{}`",
+                    name, line
+                ))),
+            }
+        };
+
+        let version = field(MAGIC)?;
+        let version = match version.parse::<u32>() {
+            Ok(number) if (OLDEST_VERSION..=SNAPSHOT_VERSION).contains(&number) => number,
+            _ => return Err(SnapshotError::UnsupportedVersion(version)),
+        };
+
+        let instruction_pointer = parse(&field("instruction_pointer")?)?;
+        let relative_base = parse(&field("relative_base")?)?;
+        let instruction_count = match version {
+            1 | 2 => 0,
+            _ => parse(&field("instruction_count")?)?,
+        };
+        let input = split(&field("input")?)?;
+        let output = split(&field("output")?)?;
+        let mut memory = Memory::from(split::<Vec<W>, W>(&field("memory")?)?);
+
+        for line in lines {
+            let line = line?;
+            let (address, values) = line
+                .strip_prefix("page ")
+                .and_then(|page| page.split_once(' '))
+                .ok_or_else(|| {
+                    SnapshotError::InvalidFormat(format!("expected `page`, found `{}`", line))
+                })?;
+            let address: usize = parse(address)?;
+            let values: Vec<W> = split(values)?;
+            for (offset, value) in values.into_iter().enumerate() {
+                let at = address
+                    .checked_add(offset)
+                    .and_then(|at| Address::try_from(at).ok())
+                    .ok_or_else(|| {
+                        SnapshotError::InvalidFormat(format!("page {} is too large", address))
+                    })?;
+                // The start of a page may overlap the dense part of memory.
+                if at as usize >= memory.as_slice().len() {
+                    memory
+                        .write(at, value)
+                        .map_err(|error| SnapshotError::InvalidFormat(error.to_string()))?;
+                }
+            }
+        }
+
+        Ok(Snapshot {
+            memory,
+            instruction_pointer,
+            relative_base,
+            instruction_count,
+            input,
+            output,
+        })
+    }
+}
+
+/// Save a snapshot to a file, in the format described on `Snapshot::write_to`.
+pub fn save_snapshot<I, O, W, P>(path: P, snapshot: &Snapshot<I, O, W>) -> io::Result<()>
+where
+    for<'a> &'a I: IntoIterator<Item = &'a W>,
+    for<'a> &'a O: IntoIterator<Item = &'a W>,
+    I: FromIterator<W>,
+    O: FromIterator<W>,
+    W: Word,
+    P: AsRef<Path>,
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    snapshot.write_to(&mut writer)?;
+    writer.flush()
+}
+
+pub fn load_snapshot<I, O, W, P>(path: P) -> Result<Snapshot<I, O, W>, SnapshotError>
+where
+    for<'a> &'a I: IntoIterator<Item = &'a W>,
+    for<'a> &'a O: IntoIterator<Item = &'a W>,
+    I: FromIterator<W>,
+    O: FromIterator<W>,
+    W: Word,
+    P: AsRef<Path>,
+{
+    Snapshot::read_from(BufReader::new(File::open(path)?))
+}
+
+fn join<'a, W: Word, T: IntoIterator<Item = &'a W>>(values: T) -> String {
+    let values: Vec<String> = values.into_iter().map(W::to_string).collect();
+    values.join(",")
+}
+
+fn parse<T: std::str::FromStr>(text: &str) -> Result<T, SnapshotError> {
+    text.parse()
+        .map_err(|_| SnapshotError::InvalidFormat(format!("invalid number `{}`", text)))
+}
+
+fn split<T: FromIterator<W>, W: Word>(text: &str) -> Result<T, SnapshotError> {
+    text.split(',')
+        .filter(|value| !value.is_empty())
+        .map(parse)
+        .collect()
+}
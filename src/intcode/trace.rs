@@ -0,0 +1,232 @@
+use super::disasm::Operand;
+use super::{
+    Address, Computer, Input, Instruction, IntCodeResult, Opcode, Output, ParameterMode, Status,
+    Value, Word,
+};
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::Write;
+use std::sync::{mpsc, Arc, Mutex};
+
+/// An operand, with the value it was resolved to: the value read, or the
+/// address written to for the last operand of instructions writing to memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TracedOperand<W = Value> {
+    pub operand: Operand<W>,
+    pub resolved: W,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryWrite<W = Value> {
+    pub address: Address,
+    pub old: W,
+    pub new: W,
+}
+
+/// One executed instruction, with the state of the registers before it was
+/// executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRecord<W = Value> {
+    pub instruction_pointer: Address,
+    pub relative_base: isize,
+    pub opcode: Opcode,
+    pub operands: Vec<TracedOperand<W>>,
+    pub write: Option<MemoryWrite<W>>,
+}
+
+impl<W> TraceRecord<W> {
+    pub fn modes(&self) -> Vec<ParameterMode> {
+        self.operands
+            .iter()
+            .map(|operand| operand.operand.mode)
+            .collect()
+    }
+}
+
+impl<W: Word> fmt::Display for TraceRecord<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>5}: {}",
+            self.instruction_pointer,
+            self.opcode.mnemonic()
+        )?;
+        let last = self.operands.len().wrapping_sub(1);
+        for (idx, traced) in self.operands.iter().enumerate() {
+            write!(f, "{}{}", if idx == 0 { " " } else { ", " }, traced.operand)?;
+            let written = self.opcode.writes_memory() && idx == last;
+            if traced.operand.mode != ParameterMode::Immediate && !written {
+                write!(f, "={}", traced.resolved)?;
+            }
+        }
+
+        write!(f, " | rb={}", self.relative_base)?;
+        if let Some(write) = &self.write {
+            write!(f, " | [{}]: {} -> {}", write.address, write.old, write.new)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Destination of the records produced by a traced `Computer`.
+pub trait TraceSink<W = Value> {
+    fn record(&mut self, record: TraceRecord<W>);
+}
+
+/// Write every record on its own line. Errors are ignored, as they would be
+/// when logging.
+#[derive(Debug)]
+pub struct TraceWriter<W>(pub W);
+
+/// Pass every record to a callback.
+#[derive(Debug, Clone)]
+pub struct TraceFn<F>(pub F);
+
+impl<W> TraceSink<W> for Vec<TraceRecord<W>> {
+    fn record(&mut self, record: TraceRecord<W>) {
+        self.push(record);
+    }
+}
+
+impl<W: Word, T: Write> TraceSink<W> for TraceWriter<T> {
+    fn record(&mut self, record: TraceRecord<W>) {
+        let _ = writeln!(self.0, "{}", record);
+    }
+}
+
+impl<W, F: FnMut(TraceRecord<W>)> TraceSink<W> for TraceFn<F> {
+    fn record(&mut self, record: TraceRecord<W>) {
+        (self.0)(record);
+    }
+}
+
+impl<W> TraceSink<W> for mpsc::Sender<TraceRecord<W>> {
+    fn record(&mut self, record: TraceRecord<W>) {
+        let _ = self.send(record);
+    }
+}
+
+/// Share a sink, to read it back while the computer owns the other handle.
+impl<W, S: TraceSink<W>> TraceSink<W> for Arc<Mutex<S>> {
+    fn record(&mut self, record: TraceRecord<W>) {
+        if let Ok(mut sink) = self.lock() {
+            sink.record(record);
+        }
+    }
+}
+
+/// Optional trace sink. It is not cloned along with its computer.
+pub(super) struct Tracer<W>(pub(super) Option<Box<dyn TraceSink<W> + Send>>);
+
+impl<W> Default for Tracer<W> {
+    fn default() -> Self {
+        Tracer(None)
+    }
+}
+
+impl<W> Clone for Tracer<W> {
+    fn clone(&self) -> Self {
+        Tracer(None)
+    }
+}
+
+impl<W> fmt::Debug for Tracer<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Tracer({})", if self.0.is_some() { "on" } else { "off" })
+    }
+}
+
+impl<I: Input<W>, O: Output<W>, W: Word> Computer<I, O, W> {
+    /// Record every executed instruction to a sink.
+    pub fn set_trace_sink<S: TraceSink<W> + Send + 'static>(&mut self, sink: S) -> &mut Self {
+        self.tracer = Tracer(Some(Box::new(sink)));
+        self
+    }
+
+    /// Stop tracing, returning the current sink.
+    pub fn take_trace_sink(&mut self) -> Option<Box<dyn TraceSink<W> + Send>> {
+        self.tracer.0.take()
+    }
+
+    pub(super) fn execute_traced(
+        &mut self,
+        blocking: bool,
+        sink: &mut dyn TraceSink<W>,
+    ) -> IntCodeResult<Status<W>> {
+        let instruction_pointer = self.instruction_pointer;
+        let relative_base = self.relative_base;
+        let instruction = match self.peek(instruction_pointer).to_value() {
+            Some(value) if !instruction_pointer.is_negative() => Instruction::try_from(value).ok(),
+            _ => None,
+        };
+        let Instruction { opcode, parameters } = match instruction {
+            Some(instruction) => instruction,
+            None => return self.execute_untraced(blocking),
+        };
+
+        // Resolve the operands before executing, as it can overwrite them.
+        let modes = [parameters.0, parameters.1, parameters.2];
+        let count = opcode.parameter_count();
+        let operands: Vec<TracedOperand<W>> = (0..count)
+            .map(|idx| {
+                let operand = Operand {
+                    mode: modes[idx],
+                    value: self.peek(instruction_pointer + 1 + idx as Address),
+                };
+                let address = match operand.mode {
+                    ParameterMode::Position => operand.value.to_value(),
+                    ParameterMode::Immediate => {
+                        let resolved = operand.value.clone();
+                        return TracedOperand { operand, resolved };
+                    }
+                    ParameterMode::Relative => operand
+                        .value
+                        .to_value()
+                        .and_then(|offset| offset.checked_add(relative_base)),
+                };
+
+                let resolved = match address {
+                    Some(address) if opcode.writes_memory() && idx + 1 == count => {
+                        W::from_value(address)
+                    }
+                    Some(address) => self.peek(address),
+                    None => W::from_value(0),
+                };
+                TracedOperand { operand, resolved }
+            })
+            .collect();
+        let old = match operands.last() {
+            Some(traced) if opcode.writes_memory() => traced
+                .resolved
+                .to_value()
+                .map_or_else(|| W::from_value(0), |address| self.peek(address)),
+            _ => W::from_value(0),
+        };
+
+        let status = self.execute_untraced(blocking)?;
+        if status != Status::NeedsInput {
+            sink.record(TraceRecord {
+                instruction_pointer,
+                relative_base,
+                opcode,
+                operands,
+                write: self.last_write.clone().map(|(address, new)| MemoryWrite {
+                    address,
+                    old,
+                    new,
+                }),
+            });
+        }
+
+        Ok(status)
+    }
+
+    fn peek(&self, address: Address) -> W {
+        if address.is_negative() {
+            W::from_value(0)
+        } else {
+            self.memory.get(address as usize)
+        }
+    }
+}
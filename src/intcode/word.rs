@@ -0,0 +1,85 @@
+use super::Value;
+use num::{BigInt, CheckedAdd, CheckedMul, ToPrimitive};
+use std::convert::TryFrom;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+
+/// Type of the values held in memory and exchanged with the I/O devices.
+///
+/// Arithmetic is checked: `None` means the result does not fit, and the
+/// computer fails with `IntCodeErrorKind::Overflow`. Implemented for `isize`
+/// (`Value`), `i64`, `i128` and `num::BigInt`, which never overflows.
+pub trait Word: Clone + Debug + Display + FromStr + Ord + Send + 'static {
+    fn from_value(value: Value) -> Self;
+
+    /// The word as a `Value`, if it fits. Addresses, opcodes and relative
+    /// base offsets must.
+    fn to_value(&self) -> Option<Value>;
+
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    fn is_zero(&self) -> bool {
+        self.to_value() == Some(0)
+    }
+
+    /// The word as a `Value`, clamped to its range, to report errors.
+    fn saturating_value(&self) -> Value {
+        self.to_value().unwrap_or_else(|| {
+            if *self < Self::from_value(0) {
+                Value::MIN
+            } else {
+                Value::MAX
+            }
+        })
+    }
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                fn from_value(value: Value) -> Self {
+                    value as $t
+                }
+
+                fn to_value(&self) -> Option<Value> {
+                    Value::try_from(*self).ok()
+                }
+
+                fn checked_add(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *other)
+                }
+
+                fn checked_mul(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_mul(*self, *other)
+                }
+
+                fn is_zero(&self) -> bool {
+                    *self == 0
+                }
+            }
+        )*
+    };
+}
+
+impl_word!(isize, i64, i128);
+
+impl Word for BigInt {
+    fn from_value(value: Value) -> Self {
+        BigInt::from(value)
+    }
+
+    fn to_value(&self) -> Option<Value> {
+        self.to_isize()
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        CheckedAdd::checked_add(self, other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        CheckedMul::checked_mul(self, other)
+    }
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use advent_of_code_2019::intcode::{read_program, Amplifiers};
+use common::program;
+
+/// Examples of day 7, with their best phase settings and signal.
+const CHAINS: &[(&str, [isize; 5], isize)] = &[
+    (
+        "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0",
+        [4, 3, 2, 1, 0],
+        43210,
+    ),
+    (
+        "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0",
+        [0, 1, 2, 3, 4],
+        54321,
+    ),
+    (
+        "3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,\
+         31,99,0,0,0",
+        [1, 0, 4, 3, 2],
+        65210,
+    ),
+];
+
+const FEEDBACK_LOOPS: &[(&str, [isize; 5], isize)] = &[
+    (
+        "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+        [9, 8, 7, 6, 5],
+        139629729,
+    ),
+    (
+        "3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,\
+         54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10",
+        [9, 7, 8, 5, 6],
+        18216,
+    ),
+];
+
+#[test]
+fn chain() {
+    for &(text, phases, signal) in CHAINS {
+        let amplifiers = Amplifiers::chain(read_program(text).unwrap());
+        assert_eq!(amplifiers.run(&phases, 0).unwrap(), Some(signal));
+        assert_eq!(
+            amplifiers.best_phases(&[0, 1, 2, 3, 4]).unwrap(),
+            Some((phases.to_vec(), signal))
+        );
+    }
+}
+
+#[test]
+fn feedback_loop() {
+    for &(text, phases, signal) in FEEDBACK_LOOPS {
+        let amplifiers = Amplifiers::feedback_loop(read_program(text).unwrap());
+        assert_eq!(amplifiers.run(&phases, 0).unwrap(), Some(signal));
+        assert_eq!(
+            amplifiers.best_phases(&[5, 6, 7, 8, 9]).unwrap(),
+            Some((phases.to_vec(), signal))
+        );
+    }
+}
+
+#[test]
+fn no_amplifiers() {
+    let amplifiers = Amplifiers::chain(read_program(CHAINS[0].0).unwrap());
+    assert_eq!(amplifiers.run(&[], 0).unwrap(), None);
+}
+
+#[test]
+fn parallel_matches_sequential() {
+    let examples = CHAINS
+        .iter()
+        .map(|&(text, ..)| {
+            (
+                Amplifiers::chain(read_program(text).unwrap()),
+                [0, 1, 2, 3, 4],
+            )
+        })
+        .chain(FEEDBACK_LOOPS.iter().map(|&(text, ..)| {
+            (
+                Amplifiers::feedback_loop(read_program(text).unwrap()),
+                [5, 6, 7, 8, 9],
+            )
+        }));
+    for (amplifiers, settings) in examples {
+        assert_eq!(
+            amplifiers.best_phases_parallel(&settings).unwrap(),
+            amplifiers.best_phases(&settings).unwrap()
+        );
+    }
+
+    let chain = Amplifiers::chain(program(7));
+    let best = chain.best_phases_parallel(&[0, 1, 2, 3, 4]).unwrap();
+    assert_eq!(best, chain.best_phases(&[0, 1, 2, 3, 4]).unwrap());
+    assert_eq!(best.unwrap().1, 422858);
+
+    let feedback_loop = Amplifiers::feedback_loop(program(7));
+    let best = feedback_loop
+        .best_phases_parallel(&[5, 6, 7, 8, 9])
+        .unwrap();
+    assert_eq!(best, feedback_loop.best_phases(&[5, 6, 7, 8, 9]).unwrap());
+    assert_eq!(best.unwrap().1, 14897241);
+}
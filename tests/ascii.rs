@@ -0,0 +1,57 @@
+use advent_of_code_2019::intcode::asm::assemble;
+use advent_of_code_2019::intcode::AsciiComputer;
+
+/// Instructions printing `text`.
+fn print(text: &str) -> String {
+    text.bytes()
+        .map(|byte| format!("out #{}\n", byte))
+        .collect()
+}
+
+fn ascii(source: &str) -> AsciiComputer {
+    AsciiComputer::new(assemble(source).unwrap())
+}
+
+#[test]
+fn read_line() {
+    let mut computer = ascii(&format!("{}hlt", print("one\n\ntwo\nthr")));
+    assert_eq!(computer.read_line().unwrap().as_deref(), Some("one"));
+    assert_eq!(computer.read_line().unwrap().as_deref(), Some(""));
+    assert_eq!(computer.read_line().unwrap().as_deref(), Some("two"));
+    // The last line is not terminated.
+    assert_eq!(computer.read_line().unwrap().as_deref(), Some("thr"));
+    assert!(computer.is_halted());
+    assert_eq!(computer.read_line().unwrap(), None);
+}
+
+#[test]
+fn read_until_prompt() {
+    let echo = "in [char]\nout [char]\n".repeat(3);
+    let mut computer = ascii(&format!(
+        "{}{}{}hlt\nchar: data 0",
+        print("Name?\n"),
+        echo,
+        print("Bye")
+    ));
+    assert_eq!(computer.read_until_prompt().unwrap(), "Name?\n");
+    assert!(!computer.is_halted());
+    // Waiting for input again gives nothing new.
+    assert_eq!(computer.read_until_prompt().unwrap(), "");
+
+    computer.send_line("hi");
+    assert_eq!(computer.read_until_prompt().unwrap(), "hi\nBye");
+    assert!(computer.is_halted());
+    assert!(computer.results().is_empty());
+}
+
+#[test]
+fn results() {
+    let mut computer = ascii(&format!(
+        "{}out #128\nout #-1\nout #19355364\n{}hlt",
+        print("a"),
+        print("b\n")
+    ));
+    // Values outside of the ASCII range do not end up in the text.
+    assert_eq!(computer.read_line().unwrap().as_deref(), Some("ab"));
+    assert_eq!(computer.results(), [128, -1, 19355364]);
+}
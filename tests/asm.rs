@@ -0,0 +1,100 @@
+mod common;
+
+use advent_of_code_2019::intcode::asm::{assemble, AsmErrorKind};
+use advent_of_code_2019::intcode::disasm::{decode, disassemble};
+use advent_of_code_2019::intcode::{Computer, Value};
+use common::program;
+use std::collections::VecDeque;
+
+const INTCODE_DAYS: [u32; 12] = [2, 5, 7, 9, 11, 13, 15, 17, 19, 21, 23, 25];
+
+#[test]
+fn round_trip() {
+    for &day in &INTCODE_DAYS {
+        let program = program(day);
+        let listing = disassemble(&program).to_string();
+        assert_eq!(assemble(&listing).unwrap(), program, "day {}", day);
+    }
+}
+
+#[test]
+fn call_and_ret() {
+    let program = assemble(
+        "
+        arb #stack
+        call double
+        out [value]
+        hlt
+        double:
+        mul [value], #2, [value]
+        ret
+        value: data 21
+        stack:
+        ",
+    )
+    .unwrap();
+
+    // call: arb #1; add #ret,#0,rb+0; jt #1,target
+    assert_eq!(program[2..11], [109, 1, 21101, 11, 0, 0, 1105, 1, 14]);
+    // ret: arb #-1; jf #0,rb+1
+    assert_eq!(program[18..23], [109, -1, 2106, 0, 1]);
+
+    let mut computer = Computer::with_io(VecDeque::<Value>::new(), Vec::new());
+    computer.load_program(program).execute().unwrap();
+    assert_eq!(computer.output_device(), &[42]);
+}
+
+#[test]
+fn undefined_label() {
+    let error = assemble("jmp nowhere\nhlt").unwrap_err();
+    assert_eq!(error.line, 1);
+    assert_eq!(
+        error.kind,
+        AsmErrorKind::UndefinedLabel("nowhere".to_string())
+    );
+}
+
+#[test]
+fn duplicate_label() {
+    let error = assemble("start: hlt\nstart: hlt").unwrap_err();
+    assert_eq!(error.line, 2);
+    assert_eq!(
+        error.kind,
+        AsmErrorKind::DuplicateLabel("start".to_string())
+    );
+}
+
+#[test]
+fn non_canonical_encodings_are_data() {
+    // Modes for parameters the opcode does not have.
+    for program in &[vec![12199], vec![10109, 5, 99], vec![1004, 0, 99]] {
+        assert_eq!(decode(program, 0), None, "{:?}", program);
+        let listing = disassemble(program).to_string();
+        assert!(listing.contains("data"), "{}", listing);
+        assert_eq!(&assemble(&listing).unwrap(), program);
+    }
+
+    assert!(decode(&[109, 5, 99], 0).is_some());
+    assert!(decode(&[104, 0, 99], 0).is_some());
+}
+
+#[test]
+fn immediate_write_target_is_data() {
+    let program = vec![10101, 1, 2, 3, 99];
+    assert_eq!(decode(&program, 0), None);
+    let listing = disassemble(&program).to_string();
+    assert_eq!(assemble(&listing).unwrap(), program);
+
+    // Immediate reads are fine.
+    assert!(decode(&[1101, 1, 2, 3], 0).is_some());
+}
+
+#[test]
+fn immediate_write_target_fails() {
+    let error = assemble("add #1, #2, #3").unwrap_err();
+    assert_eq!(error.line, 1);
+    assert_eq!(error.kind, AsmErrorKind::WriteImmediateMode);
+    let error = assemble("hlt\nin #5").unwrap_err();
+    assert_eq!(error.line, 2);
+    assert_eq!(error.kind, AsmErrorKind::WriteImmediateMode);
+}
@@ -0,0 +1,194 @@
+mod common;
+
+use advent_of_code_2019::intcode::{
+    async_channel, AsyncReceiver, AsyncSender, Computer, IntCodeErrorKind, Value,
+};
+use common::program;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+type LocalFuture<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
+
+/// Wakes a task by queuing its index.
+struct TaskWaker {
+    task: usize,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.ready.lock().unwrap().push_back(self.task);
+    }
+}
+
+/// Poll the tasks on the current thread until they are all done, panicking if
+/// they wait for each other. Finished tasks are dropped right away.
+fn run_local(tasks: Vec<LocalFuture<'_>>) {
+    let ready = Arc::new(Mutex::new((0..tasks.len()).collect::<VecDeque<usize>>()));
+    let mut tasks: Vec<Option<LocalFuture>> = tasks.into_iter().map(Some).collect();
+
+    while tasks.iter().any(Option::is_some) {
+        let task = ready
+            .lock()
+            .unwrap()
+            .pop_front()
+            .expect("tasks are deadlocked");
+        let future = match &mut tasks[task] {
+            Some(future) => future,
+            None => continue,
+        };
+
+        let waker = Waker::from(Arc::new(TaskWaker {
+            task,
+            ready: ready.clone(),
+        }));
+        let mut cx = Context::from_waker(&waker);
+        if future.as_mut().poll(&mut cx).is_ready() {
+            tasks[task] = None;
+        }
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let output = RefCell::new(None);
+    run_local(vec![Box::pin(async {
+        *output.borrow_mut() = Some(future.await);
+    })]);
+    output.into_inner().unwrap()
+}
+
+#[test]
+fn day9_with_queues() {
+    let program = program(9);
+    for &mode in &[1, 2] {
+        let mut expected = Computer::with_io(VecDeque::from(vec![mode]), Vec::new());
+        expected.load_program(program.clone()).execute().unwrap();
+
+        let mut computer = Computer::with_io(VecDeque::from(vec![mode]), Vec::new());
+        computer.load_program(program.clone());
+        block_on(computer.execute_async()).unwrap();
+        assert_eq!(computer.output_device(), expected.output_device());
+    }
+}
+
+#[test]
+fn day7_feedback_loop() {
+    let program = program(7);
+    let phases = [7, 8, 9, 6, 5];
+
+    let mut senders: Vec<AsyncSender> = Vec::new();
+    let mut receivers: Vec<AsyncReceiver> = Vec::new();
+    for &phase in &phases {
+        let (tx, rx) = async_channel();
+        tx.send(phase).unwrap();
+        senders.push(tx);
+        receivers.push(rx);
+    }
+
+    // The last amplifier outputs to the test, which feeds the first one.
+    let (last_tx, mut last_rx) = async_channel();
+    let feedback = senders[0].clone();
+    feedback.send(0).unwrap();
+    let mut outputs: Vec<AsyncSender> = senders.drain(1..).collect();
+    outputs.push(last_tx);
+    drop(senders);
+
+    let computers: Vec<_> = receivers
+        .into_iter()
+        .zip(outputs)
+        .map(|(rx, tx)| {
+            let mut computer = Computer::with_io(rx, tx);
+            computer.load_program(program.clone());
+            computer
+        })
+        .collect();
+
+    let signal = Cell::new(None);
+    let mut tasks: Vec<LocalFuture> = Vec::new();
+    tasks.push(Box::pin(async {
+        while let Some(value) = last_rx.recv().await {
+            signal.set(Some(value));
+            // The first amplifier may have halted already.
+            let _ = feedback.send(value);
+        }
+    }));
+    for mut computer in computers {
+        tasks.push(Box::pin(async move {
+            computer.execute_async().await.unwrap();
+        }));
+    }
+
+    // The feedback task stops once the last amplifier halted and was dropped.
+    run_local(tasks);
+    assert_eq!(signal.get(), Some(14897241));
+}
+
+#[test]
+fn empty_queue_fails() {
+    let mut computer = Computer::with_io(VecDeque::new(), Vec::new());
+    computer.load_program(program(9));
+    let error = block_on(computer.execute_async()).unwrap_err();
+    assert_eq!(
+        error.kind(),
+        IntCodeErrorKind::ReadError("input queue is empty")
+    );
+}
+
+#[test]
+fn closed_channel_fails() {
+    let (tx, rx) = async_channel::<Value>();
+    let (out, _outputs) = async_channel();
+    let mut computer = Computer::with_io(rx, out);
+    computer.load_program(program(9));
+
+    let mut execution = Box::pin(computer.execute_async());
+    let waker = Waker::from(Arc::new(TaskWaker {
+        task: 0,
+        ready: Arc::new(Mutex::new(VecDeque::new())),
+    }));
+    let mut cx = Context::from_waker(&waker);
+    assert!(execution.as_mut().poll(&mut cx).is_pending());
+
+    drop(tx);
+    match execution.as_mut().poll(&mut cx) {
+        Poll::Ready(Err(error)) => assert_eq!(
+            error.kind(),
+            IntCodeErrorKind::ReadError("input channel closed")
+        ),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn long_runs_yield() {
+    let program = program(9);
+    let mut computer = Computer::with_io(VecDeque::from(vec![2]), Vec::new());
+    computer.load_program(program);
+
+    // Counts how often the other task ran before the computer finished.
+    let finished = Cell::new(false);
+    let polls = Cell::new(0);
+    let other = std::future::poll_fn(|cx| {
+        if finished.get() {
+            return Poll::Ready(());
+        }
+
+        polls.set(polls.get() + 1);
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    });
+
+    run_local(vec![
+        Box::pin(async {
+            computer.execute_async().await.unwrap();
+            finished.set(true);
+        }),
+        Box::pin(other),
+    ]);
+    assert!(polls.get() > 10);
+    assert_eq!(computer.output_device(), &[82760]);
+}
@@ -0,0 +1,174 @@
+use advent_of_code_2019::intcode::asm::assemble;
+use advent_of_code_2019::intcode::cfg::{Cfg, Edge, EdgeKind, WriteToCode};
+use std::collections::BTreeSet;
+
+const BRANCH: &str = "
+    in [x]
+    jt [x], #skip
+    out #1
+skip:
+    out #2
+    hlt
+x: data 0
+";
+
+const CALL: &str = "
+    arb #stack
+    call double
+    out [value]
+    hlt
+double:
+    mul [value], #2, [value]
+    ret
+value: data 21
+stack:
+";
+
+fn cfg(source: &str) -> Cfg {
+    Cfg::new(&assemble(source).unwrap())
+}
+
+fn edges(cfg: &Cfg) -> BTreeSet<(usize, usize, EdgeKind)> {
+    cfg.edges
+        .iter()
+        .map(|&Edge { from, to, kind }| (from, to, kind))
+        .collect()
+}
+
+#[test]
+fn blocks_and_edges() {
+    let cfg = cfg(BRANCH);
+    let blocks: Vec<(usize, usize)> = cfg
+        .blocks
+        .values()
+        .map(|block| (block.start(), block.end()))
+        .collect();
+    assert_eq!(blocks, [(0, 5), (5, 7), (7, 10)]);
+    assert_eq!(
+        edges(&cfg),
+        [
+            (0, 5, EdgeKind::FallThrough),
+            (0, 7, EdgeKind::Branch),
+            (5, 7, EdgeKind::FallThrough),
+        ]
+        .iter()
+        .copied()
+        .collect()
+    );
+
+    assert_eq!(cfg.block_at(7).unwrap().start(), 7);
+    assert!(cfg.block_at(8).is_none());
+    assert_eq!(cfg.instruction_including(8).unwrap().address, 7);
+    assert_eq!(cfg.unreachable(11), [10]);
+    assert_eq!(cfg.functions.keys().copied().collect::<Vec<_>>(), [0]);
+    assert!(cfg.writes_to_code().is_empty());
+}
+
+#[test]
+fn calls_and_returns() {
+    let cfg = cfg(CALL);
+    let call = &cfg.blocks[&0];
+    assert!(call.is_call());
+    assert_eq!(call.end(), 11);
+    assert!(cfg.blocks[&14].is_return());
+    assert!(!cfg.blocks[&14].is_indirect());
+    assert_eq!(
+        edges(&cfg),
+        [
+            (0, 11, EdgeKind::CallReturn),
+            (0, 14, EdgeKind::Call),
+            (14, 11, EdgeKind::Return),
+        ]
+        .iter()
+        .copied()
+        .collect()
+    );
+
+    // The function does not include its caller.
+    assert_eq!(cfg.functions.len(), 2);
+    assert_eq!(
+        cfg.functions[&0].iter().copied().collect::<Vec<_>>(),
+        [0, 11]
+    );
+    assert_eq!(cfg.functions[&14].iter().copied().collect::<Vec<_>>(), [14]);
+}
+
+#[test]
+fn writes_to_code() {
+    // Patches the opcode of the first instruction, then the target of a jump.
+    let cfg = cfg("
+start:
+patch: add #6, #7, [result]
+    add #1102, #0, [patch]
+    add #done, #0, [jump+2]
+jump: jt #1, #start
+done:
+    hlt
+result: data 0
+");
+    assert_eq!(
+        cfg.writes_to_code(),
+        [
+            WriteToCode {
+                address: 4,
+                target: 0,
+                instruction: 0,
+            },
+            WriteToCode {
+                address: 8,
+                target: 14,
+                instruction: 12,
+            },
+        ]
+    );
+}
+
+#[test]
+fn dot() {
+    let dot = cfg(CALL).to_dot();
+    assert!(dot.starts_with("digraph cfg {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains("    subgraph cluster_14 {\n        label=\"function 14\";\n"));
+    assert!(dot.contains("b14 [label=\"   14: mul [23], #2, [23]\\l"));
+    assert!(dot.contains("    b0 -> b14 [color=blue, style=bold];\n"));
+    assert!(dot.contains("    b0 -> b11 [style=dashed];\n"));
+    assert!(dot.contains("    b14 -> b11 [color=red, style=dotted];\n"));
+
+    // Jumps to a computed address are marked.
+    let dot = cfg("jt #1, [target]\nhlt\ntarget: data 3").to_dot();
+    assert!(dot.contains("(indirect jump)"));
+}
+
+#[test]
+fn jump_table() {
+    let cfg = cfg("
+    in [x]
+    add [x], #table, [jump+2]
+jump:
+    jt #1, [0]
+table:
+    data one, two
+one:
+    out #1
+    hlt
+two:
+    out #2
+    hlt
+x: data 0
+");
+    let table = &cfg.tables[&0];
+    assert_eq!(table.start, 9);
+    assert_eq!(table.targets, [11, 14]);
+    assert_eq!(
+        edges(&cfg),
+        [(0, 11, EdgeKind::Table), (0, 14, EdgeKind::Table)]
+            .iter()
+            .copied()
+            .collect()
+    );
+    assert_eq!(
+        cfg.functions[&0].iter().copied().collect::<Vec<_>>(),
+        [0, 11, 14]
+    );
+    assert!(cfg.to_dot().contains("    b0 -> b14 [color=purple];\n"));
+}
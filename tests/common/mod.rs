@@ -0,0 +1,8 @@
+use advent_of_code_2019::intcode::{read_program, Program};
+
+/// The puzzle input of a day.
+pub fn program(day: u32) -> Program {
+    let path = format!("inputs/day{}.txt", day);
+    let text = std::fs::read_to_string(&path).unwrap();
+    read_program(&text).unwrap()
+}
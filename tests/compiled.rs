@@ -0,0 +1,130 @@
+mod common;
+
+use advent_of_code_2019::intcode::{CompiledProgram, Computer, Program, Value};
+use common::program;
+use std::collections::VecDeque;
+
+type Machine = Computer<VecDeque<Value>, Vec<Value>>;
+
+const INSTRUCTION_LIMIT: u64 = 50_000_000;
+
+fn machine(program: &Program, patches: &[(Value, Value)], input: &[Value]) -> Machine {
+    let mut computer = Computer::with_io(input.iter().copied().collect(), Vec::new());
+    computer
+        .set_instruction_limit(Some(INSTRUCTION_LIMIT))
+        .load_program(program.clone());
+    for &(address, value) in patches {
+        computer.write_memory(address, value).unwrap();
+    }
+
+    computer
+}
+
+fn ascii(text: &str) -> Vec<Value> {
+    text.bytes().map(Value::from).collect()
+}
+
+/// Run the program with both engines and compare everything observable. The
+/// program is compiled before patching, so patched blocks are interpreted.
+fn compare(day: u32, patches: &[(Value, Value)], input: &[Value]) {
+    let program = program(day);
+    let compiled = CompiledProgram::new(&program);
+    assert!(compiled.block_count() > 0);
+
+    let mut interpreted = machine(&program, patches, input);
+    let expected = interpreted.execute();
+
+    let mut computer = machine(&program, patches, input);
+    let result = compiled.execute(&mut computer);
+
+    assert_eq!(result, expected, "day {}, input {:?}", day, input);
+    assert_eq!(computer.output_device(), interpreted.output_device());
+    assert_eq!(computer.input_device(), interpreted.input_device());
+    assert_eq!(
+        computer.instruction_count(),
+        interpreted.instruction_count()
+    );
+    if expected.is_ok() {
+        assert_eq!(computer.memory(), interpreted.memory());
+        assert_eq!(
+            computer.instruction_pointer(),
+            interpreted.instruction_pointer()
+        );
+        assert_eq!(computer.relative_base(), interpreted.relative_base());
+    }
+}
+
+#[test]
+fn day2() {
+    compare(2, &[(1, 12), (2, 2)], &[]);
+    compare(2, &[(1, 77), (2, 49)], &[]);
+    compare(2, &[(1, 0), (2, 0)], &[]);
+}
+
+#[test]
+fn day5() {
+    compare(5, &[], &[1]);
+    compare(5, &[], &[5]);
+}
+
+#[test]
+fn day7() {
+    compare(7, &[], &[0, 0]);
+    compare(7, &[], &[4, 17]);
+    compare(7, &[], &[5, 0]);
+}
+
+#[test]
+fn day9() {
+    compare(9, &[], &[1]);
+    compare(9, &[], &[2]);
+}
+
+#[test]
+fn day11() {
+    let colors: Vec<Value> = (0..200).map(|idx| (idx * 7 % 3 == 0) as Value).collect();
+    compare(11, &[], &colors);
+}
+
+#[test]
+fn day13() {
+    compare(13, &[], &[]);
+    compare(13, &[(0, 2)], &[0; 500]);
+}
+
+#[test]
+fn day15() {
+    let moves: Vec<Value> = (0..500).map(|idx| (idx * idx + idx / 3) % 4 + 1).collect();
+    compare(15, &[], &moves);
+}
+
+#[test]
+fn day17() {
+    compare(17, &[], &[]);
+    compare(17, &[(0, 2)], &ascii("A\nL,1\nL,1\nL,1\nn\n"));
+}
+
+#[test]
+fn day19() {
+    compare(19, &[], &[0, 0]);
+    compare(19, &[], &[10, 12]);
+    compare(19, &[], &[30, 40]);
+}
+
+#[test]
+fn day21() {
+    compare(21, &[], &ascii("NOT A J\nWALK\n"));
+    compare(21, &[], &ascii("NOT A J\nRUN\n"));
+}
+
+#[test]
+fn day23() {
+    let mut input = vec![3];
+    input.extend(std::iter::repeat_n(-1, 50));
+    compare(23, &[], &input);
+}
+
+#[test]
+fn day25() {
+    compare(25, &[], &ascii("north\nsouth\nwest\ninv\n"));
+}
@@ -0,0 +1,193 @@
+mod common;
+
+use advent_of_code_2019::intcode::asm::assemble;
+use advent_of_code_2019::intcode::decompile::decompile;
+use advent_of_code_2019::intcode::read_program;
+use common::program;
+
+fn decompiled(source: &str) -> String {
+    decompile(&assemble(source).unwrap()).to_string()
+}
+
+#[test]
+fn if_else() {
+    let text = decompiled(
+        "
+    in [x]
+    jf [x], #else
+    out #1
+    jmp end
+else:
+    out #2
+end:
+    hlt
+x: data 0
+",
+    );
+    assert_eq!(
+        text,
+        "\
+fn main() {
+    mem[13] = input();
+    if mem[13] != 0 {
+        output(1);
+    } else {
+        output(2);
+    }
+    halt();
+}
+"
+    );
+}
+
+#[test]
+fn countdown_loop() {
+    let text = decompiled(
+        "
+    in [n]
+loop:
+    out [n]
+    add [n], #-1, [n]
+    jt [n], #loop
+    hlt
+n: data 0
+",
+    );
+    assert_eq!(
+        text,
+        "\
+fn main() {
+    mem[12] = input();
+    loop {
+        output(mem[12]);
+        mem[12] -= 1;
+        if mem[12] == 0 {
+            break;
+        }
+    }
+    halt();
+}
+"
+    );
+}
+
+#[test]
+fn call() {
+    let text = decompiled(
+        "
+    arb #stack
+    call double
+    out [value]
+    hlt
+double:
+    mul [value], #2, [value]
+    ret
+value: data 21
+stack:
+",
+    );
+    assert_eq!(
+        text,
+        "\
+fn main() {
+    func_14();
+    output(mem[23]);
+    halt();
+}
+
+fn func_14() {
+    mem[23] *= 2;
+    return;
+}
+"
+    );
+}
+
+#[test]
+fn jump_table() {
+    // The jump goes to the entry of the table at the input: its operand is
+    // patched with the address of that entry.
+    let text = decompiled(
+        "
+    in [x]
+    add [x], #table, [jump+2]
+jump:
+    jt #1, [0]
+table:
+    data one, two
+one:
+    out #1
+    hlt
+two:
+    out #2
+    hlt
+x: data 0
+",
+    );
+    assert_eq!(
+        text,
+        "\
+fn main() {
+    mem[17] = input();
+    mem[8] = mem[17] + 9;
+    match mem[8] - 9 {
+        0 => goto label_11;
+        1 => goto label_14;
+    }
+    label_11:
+    output(1);
+    halt();
+    label_14:
+    output(2);
+    halt();
+}
+"
+    );
+}
+
+#[test]
+fn block_past_region_end() {
+    // The jump skips to the middle of the instruction after it, so the block
+    // of that instruction ends past the region of the `if`.
+    let program = read_program("1005,39,7,9,1,5,1108,3,-59,1109").unwrap();
+    let text = decompile(&program).to_string();
+    assert!(text.starts_with("fn main() {"));
+    assert!(text.contains("rb += mem[1];"));
+    assert!(text.contains("mem[-59] = input();"));
+}
+
+#[test]
+fn day9() {
+    let text = decompile(&program(9)).to_string();
+    assert!(text.starts_with("fn main() {"));
+    assert!(text.contains("rb[0] = input();"));
+    assert_eq!(text.matches('{').count(), text.matches('}').count());
+}
+
+#[test]
+fn jump_table_inputs() {
+    // Days 7 and 23 dispatch on their first input through a jump table.
+    for &(day, entries) in &[(7, 10), (23, 50)] {
+        let text = decompile(&program(day)).to_string();
+        assert_eq!(
+            text.matches(" => goto label_").count(),
+            entries,
+            "day {}",
+            day
+        );
+        assert!(text.lines().count() > 200, "day {}", day);
+    }
+
+    let day7 = decompile(&program(7)).to_string();
+    assert_eq!(day7.matches("output(mem[9]);").count(), 55);
+    let day23 = decompile(&program(23)).to_string();
+    assert!(day23.contains("func_436(mem[2244], mem[2245]);"));
+}
+
+#[test]
+fn all_inputs() {
+    for day in &[2, 5, 7, 9, 11, 13, 15, 17, 19, 21, 23, 25] {
+        let text = decompile(&program(*day)).to_string();
+        assert!(text.contains("fn main() {"), "day {}", day);
+    }
+}
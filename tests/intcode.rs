@@ -0,0 +1,359 @@
+mod common;
+
+use advent_of_code_2019::intcode::asm::assemble;
+use advent_of_code_2019::intcode::{Address, Computer, IntCodeErrorKind, Status, Value, PAGE_SIZE};
+use common::program;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+type Machine = Computer<VecDeque<Value>, Vec<Value>>;
+
+fn machine(program: &[Value], input: &[Value]) -> Machine {
+    let mut computer = Computer::with_io(input.iter().copied().collect(), Vec::new());
+    computer.load_program(program.to_vec());
+    computer
+}
+
+#[test]
+fn code_writes_far_away() {
+    // Jump far away, where the instruction overwrites its own opcode.
+    const FAR: Address = 1 << 40;
+    let mut computer = machine(&[1106, 0, FAR], &[]);
+    computer.set_self_modification_detection(true);
+    for (offset, &value) in [1101, 1, 1, FAR, 99].iter().enumerate() {
+        computer
+            .write_memory(FAR + offset as Address, value)
+            .unwrap();
+    }
+
+    computer.execute().unwrap();
+    let writes = computer.code_writes();
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].instruction_pointer, FAR);
+    assert_eq!(writes[0].address, FAR);
+    assert_eq!(writes[0].instruction, FAR);
+}
+
+#[test]
+fn no_code_writes_in_day9() {
+    let mut computer = machine(&program(9), &[1]);
+    computer
+        .set_self_modification_detection(true)
+        .execute()
+        .unwrap();
+    assert!(computer.code_writes().is_empty());
+}
+
+#[test]
+fn coverage_far_away() {
+    const FAR: Address = 1 << 40;
+    let mut computer = machine(&[1106, 0, FAR], &[]);
+    computer.set_coverage(true);
+    computer.write_memory(FAR, 99).unwrap();
+    computer.execute().unwrap();
+
+    let coverage = computer.coverage().unwrap();
+    assert_eq!(coverage.covered().collect::<Vec<_>>(), [(0, 1), (FAR, 1)]);
+    assert_eq!(coverage.uncovered(&[1106, 0, FAR]), []);
+
+    let mut merged = coverage.clone();
+    merged.merge(coverage);
+    assert_eq!(merged.hits(FAR), 2);
+}
+
+#[test]
+fn profile_far_away() {
+    const FAR: Address = 1 << 40;
+    let mut computer = machine(&[1106, 0, FAR], &[]);
+    computer.set_profiling(true);
+    for (offset, &value) in [1101, 1, 1, 0, 99].iter().enumerate() {
+        computer
+            .write_memory(FAR + offset as Address, value)
+            .unwrap();
+    }
+
+    computer.execute().unwrap();
+    let profile = computer.profile();
+    assert_eq!(profile.instruction_count(), 2);
+    assert_eq!(profile.counts().collect::<Vec<_>>(), [(0, 1), (FAR, 1)]);
+    assert!(profile.report(&[1106, 0, FAR], 20).contains("Hot spots:"));
+}
+
+/// Execute `count` instructions, returning their outputs.
+fn steps(computer: &mut Machine, count: u64) -> Vec<Value> {
+    let mut outputs = Vec::new();
+    for _ in 0..count {
+        if let Status::Output(value) = computer.step().unwrap() {
+            outputs.push(value);
+        }
+    }
+
+    outputs
+}
+
+#[test]
+fn rewind_matches_fresh_run() {
+    let program = program(9);
+    // Before and after the input is read.
+    for &stop in &[5, 12345] {
+        let mut computer = machine(&program, &[2]);
+        computer.set_history(true);
+        steps(&mut computer, 20000);
+        computer.rewind_to(stop).unwrap();
+
+        let mut fresh = machine(&program, &[2]);
+        steps(&mut fresh, stop);
+        assert_eq!(computer.instruction_count(), stop);
+        assert_eq!(computer.instruction_pointer(), fresh.instruction_pointer());
+        assert_eq!(computer.relative_base(), fresh.relative_base());
+        // Memory grown by undone writes stays allocated.
+        let len = computer.memory().len().max(fresh.memory().len());
+        for address in 0..len {
+            assert_eq!(
+                computer.memory().get(address),
+                fresh.memory().get(address),
+                "address {}",
+                address
+            );
+        }
+
+        // The input read by undone instructions is read again.
+        computer.execute().unwrap();
+        fresh.execute().unwrap();
+        assert_eq!(computer.output_device(), &[82760]);
+        assert_eq!(computer.output_device(), fresh.output_device());
+        assert_eq!(computer.instruction_count(), fresh.instruction_count());
+    }
+}
+
+#[test]
+fn last_writer() {
+    let mut computer = machine(&program(9), &[2]);
+    computer.set_history(true);
+    let mut writes = 0;
+    for _ in 0..1000 {
+        computer.step().unwrap();
+        if let Some((address, value)) = computer.last_write() {
+            let entry = computer.last_writer(address).unwrap();
+            assert_eq!(entry.instruction_count, computer.instruction_count() - 1);
+            assert_eq!(entry.write.as_ref().unwrap().new, value);
+            writes += 1;
+        }
+    }
+
+    assert!(writes > 0);
+    assert!(computer.last_writer(-1).is_none());
+    computer.step_back().unwrap();
+    assert_eq!(computer.instruction_count(), 999);
+}
+
+#[test]
+fn rewind_outside_history() {
+    let mut computer = machine(&program(9), &[2]);
+    steps(&mut computer, 10);
+    computer.set_history(true);
+    steps(&mut computer, 10);
+    assert!(computer.rewind_to(15).is_ok());
+    assert_eq!(
+        computer.rewind_to(5).unwrap_err().kind(),
+        IntCodeErrorKind::NotInHistory(5)
+    );
+    assert_eq!(
+        computer.rewind_to(30).unwrap_err().kind(),
+        IntCodeErrorKind::NotInHistory(30)
+    );
+}
+
+/// Outputs 6 + 7, then turns the addition into a multiplication and runs it
+/// again.
+const PATCH_OPCODE: &str = "
+start:
+patch: add #6, #7, [result]
+    out [result]
+    jt [patched], #done
+    add #1, #0, [patched]
+    add #1102, #0, [patch]
+    jmp start
+done:
+    hlt
+result: data 0
+patched: data 0
+";
+
+#[test]
+fn cache_sees_code_writes() {
+    let program = assemble(PATCH_OPCODE).unwrap();
+    for &cache in &[true, false] {
+        let mut computer = machine(&program, &[]);
+        computer.set_instruction_cache(cache).execute().unwrap();
+        assert_eq!(computer.output_device(), &[13, 42], "cache {}", cache);
+    }
+}
+
+#[test]
+fn cache_sees_write_memory() {
+    let program = assemble(PATCH_OPCODE).unwrap();
+    let mut computer = machine(&program, &[]);
+    computer.execute().unwrap();
+
+    // Back to an addition, run once as `patched` is still set.
+    computer.write_memory(0, 1101).unwrap();
+    computer.write_memory(1, 30).unwrap();
+    computer.reset().execute().unwrap();
+    assert_eq!(computer.output_device(), &[13, 42, 37]);
+}
+
+/// Jumps to itself forever.
+const LOOP: [Value; 3] = [1105, 1, 0];
+
+#[test]
+fn instruction_limit() {
+    let mut computer = machine(&LOOP, &[]);
+    computer.set_instruction_limit(Some(1000));
+    let error = computer.execute().unwrap_err();
+    assert_eq!(error.kind(), IntCodeErrorKind::InstructionLimit(1000));
+    assert_eq!(computer.instruction_count(), 1000);
+
+    // The program stops exactly at the limit.
+    let mut computer = machine(&[1101, 1, 2, 5, 99, 0], &[]);
+    computer
+        .set_instruction_limit(Some(1))
+        .execute()
+        .unwrap_err();
+    assert_eq!(computer.memory().get(5), 3);
+    assert_eq!(computer.instruction_count(), 1);
+    computer.set_instruction_limit(Some(2)).execute().unwrap();
+}
+
+#[test]
+fn deadline() {
+    let mut computer = machine(&LOOP, &[]);
+    computer.set_deadline(Some(Instant::now()));
+    let error = computer.execute().unwrap_err();
+    assert_eq!(error.kind(), IntCodeErrorKind::DeadlineExceeded);
+    assert_eq!(computer.instruction_count() % 1024, 0);
+
+    let mut computer = machine(&LOOP, &[]);
+    computer.set_timeout(Duration::from_millis(10));
+    let error = computer.execute().unwrap_err();
+    assert_eq!(error.kind(), IntCodeErrorKind::DeadlineExceeded);
+    assert!(computer.instruction_count() > 0);
+}
+
+#[test]
+fn invalid_opcode_context() {
+    let mut computer = machine(&[1101, 1, 2, 0, 98], &[]);
+    let error = computer.execute().unwrap_err();
+    assert_eq!(error.kind(), IntCodeErrorKind::InvalidOpCode(98));
+
+    let context = error.context().unwrap();
+    assert_eq!(context.instruction_pointer, 4);
+    assert_eq!(context.instruction, 98);
+    assert_eq!(context.relative_base, 0);
+    assert_eq!(context.instruction_count, 1);
+    assert_eq!(context.window_start, 0);
+    assert_eq!(context.memory, [3, 1, 2, 0, 98]);
+    assert!(error.to_string().contains(" >98<"));
+}
+
+#[test]
+fn negative_address_context() {
+    let mut computer = machine(&[1, -1, 0, 0, 99], &[]);
+    let error = computer.execute().unwrap_err();
+    assert_eq!(error.kind(), IntCodeErrorKind::InvalidAddress(-1));
+    let context = error.context().unwrap();
+    assert_eq!(context.instruction_pointer, 0);
+    assert_eq!(context.instruction, 1);
+    assert_eq!(context.instruction_count, 0);
+
+    // Relative to a negative base.
+    let mut computer = machine(&[109, -10, 204, 0, 99], &[]);
+    let error = computer.execute().unwrap_err();
+    assert_eq!(error.kind(), IntCodeErrorKind::InvalidAddress(-10));
+    let context = error.context().unwrap();
+    assert_eq!(context.instruction_pointer, 2);
+    assert_eq!(context.instruction, 204);
+    assert_eq!(context.relative_base, -10);
+    assert_eq!(context.instruction_count, 1);
+    assert_eq!(context.memory, [109, -10, 204, 0, 99]);
+}
+
+#[test]
+fn max_address() {
+    // Writing just below, at and past the limit.
+    let mut computer = machine(&[1101, 1, 1, 100, 1101, 1, 1, 101, 99], &[]);
+    computer.set_max_address(Some(100));
+    let error = computer.execute().unwrap_err();
+    assert_eq!(error.kind(), IntCodeErrorKind::InvalidAddress(101));
+    assert_eq!(error.context().unwrap().instruction_pointer, 4);
+    assert_eq!(computer.memory().get(100), 2);
+
+    // Reading past it.
+    let mut computer = machine(&[4, 200, 99], &[]);
+    computer.set_max_address(Some(100));
+    let error = computer.execute().unwrap_err();
+    assert_eq!(error.kind(), IntCodeErrorKind::InvalidAddress(200));
+
+    // Without a limit, the same reads 0.
+    let mut computer = machine(&[4, 200, 99], &[]);
+    computer.execute().unwrap();
+    assert_eq!(computer.output_device(), &[0]);
+}
+
+#[test]
+fn max_address_relative_write() {
+    // arb #90; add #1, #1, rb+11
+    let mut computer = machine(&[109, 90, 21101, 1, 1, 11, 99], &[]);
+    computer.set_max_address(Some(100));
+    let error = computer.execute().unwrap_err();
+    assert_eq!(error.kind(), IntCodeErrorKind::InvalidAddress(101));
+    assert_eq!(error.context().unwrap().relative_base, 90);
+
+    let mut computer = machine(&[109, 90, 21101, 1, 1, 10, 99], &[]);
+    computer.set_max_address(Some(100)).execute().unwrap();
+    assert_eq!(computer.memory().get(100), 2);
+}
+
+#[test]
+fn far_sparse_pages() {
+    const FAR: Address = 1 << 40;
+    // add #7, #0, [FAR]; add #8, #0, [FAR+PAGE_SIZE+1]; out [FAR]; out [FAR+1]
+    let second = FAR + PAGE_SIZE as Address + 1;
+    let program = [
+        1101,
+        7,
+        0,
+        FAR,
+        1101,
+        8,
+        0,
+        second,
+        4,
+        FAR,
+        4,
+        second,
+        4,
+        FAR + 1,
+        99,
+    ];
+    let mut computer = machine(&program, &[]);
+    computer.execute().unwrap();
+    assert_eq!(computer.output_device(), &[7, 8, 0]);
+
+    let memory = computer.memory();
+    let pages: Vec<usize> = memory.pages().map(|(address, _)| address).collect();
+    assert_eq!(pages, [FAR as usize, FAR as usize + PAGE_SIZE]);
+    assert_eq!(memory.get(FAR as usize), 7);
+    assert_eq!(memory.get(second as usize), 8);
+    assert_eq!(memory.get(FAR as usize - 1), 0);
+    assert_eq!(memory.len(), FAR as usize + 2 * PAGE_SIZE);
+
+    // Far pages are subject to the limit too.
+    let mut computer = machine(&program, &[]);
+    computer.set_max_address(Some(FAR as usize));
+    assert_eq!(
+        computer.execute().unwrap_err().kind(),
+        IntCodeErrorKind::InvalidAddress(second)
+    );
+    assert_eq!(computer.memory().get(FAR as usize), 7);
+}
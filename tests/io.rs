@@ -0,0 +1,93 @@
+use advent_of_code_2019::intcode::{
+    Computer, Input, InputFn, InputIter, IntCodeErrorKind, Output, OutputFn, Status, Value,
+};
+use std::collections::VecDeque;
+
+/// Read two values and output their sum.
+const SUM: [Value; 14] = [3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0];
+
+fn run<I: Input, O: Output>(input: I, output: O) -> Result<(), IntCodeErrorKind> {
+    let mut computer = Computer::with_io(input, output);
+    computer.load_program(SUM.to_vec());
+    computer.execute().map_err(|error| error.kind())
+}
+
+#[test]
+fn input_iter() {
+    let mut output = Vec::new();
+    run(InputIter(vec![2, 3].into_iter()), &mut output).unwrap();
+    assert_eq!(output, [5]);
+
+    assert_eq!(
+        run(InputIter(Some(2).into_iter()), Vec::new()),
+        Err(IntCodeErrorKind::ReadError("input iterator is exhausted"))
+    );
+}
+
+#[test]
+fn input_fn() {
+    let mut values = vec![4, 3];
+    let mut output = Vec::new();
+    run(InputFn(|| values.pop()), &mut output).unwrap();
+    assert_eq!(output, [7]);
+
+    // A callback can only say no value is available yet, which is an error
+    // for a blocking read.
+    assert_eq!(
+        run(InputFn(|| None), Vec::new()),
+        Err(IntCodeErrorKind::ReadError("no input available"))
+    );
+
+    // Stepping waits for it instead.
+    let mut computer = Computer::with_io(InputFn(|| None), Vec::new());
+    computer.load_program(SUM.to_vec());
+    assert_eq!(computer.run().unwrap(), Status::NeedsInput);
+    assert_eq!(computer.instruction_count(), 0);
+}
+
+#[test]
+fn output_fn() {
+    let mut outputs = Vec::new();
+    run(
+        VecDeque::from(vec![20, 22]),
+        OutputFn(|value| outputs.push(value)),
+    )
+    .unwrap();
+    assert_eq!(outputs, [42]);
+}
+
+#[test]
+fn vec_deque() {
+    let mut input = VecDeque::from(vec![1, 2, 3]);
+    let mut output = VecDeque::new();
+    run(&mut input, &mut output).unwrap();
+    assert_eq!(input, [3]);
+    assert_eq!(output, [3]);
+
+    assert_eq!(
+        run(VecDeque::from(vec![1]), Vec::new()),
+        Err(IntCodeErrorKind::ReadError("input queue is empty"))
+    );
+}
+
+#[test]
+fn disconnected_channels() {
+    let (mut computer, tx, _rx) = Computer::new();
+    computer.load_program(SUM.to_vec());
+    tx.send(1).unwrap();
+    drop(tx);
+    assert_eq!(
+        computer.execute().unwrap_err().kind(),
+        IntCodeErrorKind::ReadError("input channel disconnected")
+    );
+
+    let (mut computer, tx, rx) = Computer::new();
+    computer.load_program(SUM.to_vec());
+    tx.send(1).unwrap();
+    tx.send(2).unwrap();
+    drop(rx);
+    assert_eq!(
+        computer.execute().unwrap_err().kind(),
+        IntCodeErrorKind::WriteError("output channel disconnected")
+    );
+}
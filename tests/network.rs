@@ -0,0 +1,119 @@
+use advent_of_code_2019::intcode::asm::assemble;
+use advent_of_code_2019::intcode::{Nat, Network, Packet, NAT_ADDRESS};
+use std::sync::{Arc, Mutex};
+
+/// Sends `(address, 7)` to the NAT, then forwards every packet received to it.
+const FORWARD: &str = "
+    in [address]
+    out #255
+    out [address]
+    out #7
+wait:
+    in [x]
+    eq [x], #-1, [idle]
+    jt [idle], #wait
+    in [y]
+    out #255
+    out [x]
+    out [y]
+    jmp wait
+address: data 0
+x: data 0
+y: data 0
+idle: data 0
+";
+
+/// Logs what it receives and the rounds it is woken up in, and sends a single
+/// packet to address 0.
+#[derive(Debug, Default)]
+struct StubNat {
+    received: Arc<Mutex<Vec<Packet>>>,
+    woken: Arc<Mutex<Vec<u64>>>,
+    sent: bool,
+}
+
+impl Nat for StubNat {
+    fn receive(&mut self, packet: Packet) {
+        self.received.lock().unwrap().push(packet);
+    }
+
+    fn wake(&mut self, round: u64) -> Option<Packet> {
+        self.woken.lock().unwrap().push(round);
+        if self.sent {
+            return None;
+        }
+
+        self.sent = true;
+        Some(Packet {
+            round,
+            source: NAT_ADDRESS,
+            destination: 0,
+            x: 100,
+            y: 200,
+        })
+    }
+}
+
+#[test]
+fn nat_wakes_when_idle() {
+    let nat = StubNat::default();
+    let received = nat.received.clone();
+    let woken = nat.woken.clone();
+    let mut network = Network::new(&assemble(FORWARD).unwrap(), 2);
+    network.set_nat(nat);
+
+    // Every computer sends a packet, so the NAT is not woken up.
+    assert!(!network.run_round().unwrap());
+    assert!(woken.lock().unwrap().is_empty());
+    assert_eq!(received.lock().unwrap().len(), 2);
+    assert_eq!(network.packets().len(), 2);
+
+    // Idle: the NAT sends its packet.
+    assert!(network.run_round().unwrap());
+    assert_eq!(*woken.lock().unwrap(), [1]);
+    let injected = network.packets()[2];
+    assert_eq!(injected.source, NAT_ADDRESS);
+    assert_eq!(injected.destination, 0);
+    assert_eq!(injected.round, 1);
+
+    // Computer 0 forwards it back.
+    assert!(!network.run_round().unwrap());
+    assert_eq!(*woken.lock().unwrap(), [1]);
+    assert_eq!(
+        network.packets()[3],
+        Packet {
+            round: 2,
+            source: 0,
+            destination: NAT_ADDRESS,
+            x: 100,
+            y: 200,
+        }
+    );
+    assert_eq!(received.lock().unwrap().len(), 3);
+
+    // Idle again, with nothing left to send.
+    assert!(network.run_round().unwrap());
+    assert_eq!(*woken.lock().unwrap(), [1, 3]);
+    assert_eq!(network.packets().len(), 4);
+    assert_eq!(network.rounds(), 4);
+}
+
+#[test]
+fn packets_without_nat() {
+    let mut network = Network::new(&assemble(FORWARD).unwrap(), 3);
+    assert!(!network.run_round().unwrap());
+    assert!(network.run_round().unwrap());
+    let packets: Vec<_> = network
+        .packets()
+        .iter()
+        .map(|packet| (packet.source, packet.destination, packet.x, packet.y))
+        .collect();
+    assert_eq!(
+        packets,
+        [
+            (0, NAT_ADDRESS, 0, 7),
+            (1, NAT_ADDRESS, 1, 7),
+            (2, NAT_ADDRESS, 2, 7)
+        ]
+    );
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use advent_of_code_2019::intcode::{ScheduleEvent, Scheduler, SeededRandom, Value};
+use common::program;
+
+const PHASES: [Value; 5] = [7, 8, 9, 6, 5];
+
+/// Run the day 7 feedback loop with a random policy and short time slices,
+/// returning the trace and the final signal.
+fn feedback_loop(seed: u64) -> (Vec<ScheduleEvent>, Option<Value>) {
+    let program = program(7);
+    let mut scheduler = Scheduler::new();
+    scheduler
+        .set_policy(SeededRandom::new(seed))
+        .set_time_slice(Some(5));
+    for &phase in &PHASES {
+        let id = scheduler.add(program.clone());
+        scheduler.send(id, phase);
+    }
+
+    for id in 1..PHASES.len() {
+        scheduler.connect(id - 1, id);
+    }
+
+    scheduler
+        .connect(PHASES.len() - 1, 0)
+        .send(0, 0)
+        .run()
+        .unwrap();
+    assert!(scheduler.all_halted());
+    let signal = scheduler.machine(0).input_device().back().copied();
+    (scheduler.trace().to_vec(), signal)
+}
+
+#[test]
+fn same_seed_same_interleaving() {
+    let (trace, signal) = feedback_loop(42);
+    assert_eq!(feedback_loop(42), (trace, signal));
+}
+
+#[test]
+fn different_seeds_differ() {
+    let (trace, signal) = feedback_loop(1);
+    let (other_trace, other_signal) = feedback_loop(2);
+    assert_ne!(trace, other_trace);
+
+    // The result does not depend on the interleaving.
+    assert_eq!(signal, Some(14897241));
+    assert_eq!(other_signal, signal);
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use advent_of_code_2019::intcode::{
+    load_snapshot, save_snapshot, Computer, IntCodeErrorKind, Snapshot, SnapshotError, Value,
+};
+use common::program;
+use std::collections::VecDeque;
+
+type Machine = Computer<VecDeque<Value>, VecDeque<Value>>;
+
+fn read(text: &str) -> Result<Snapshot, SnapshotError> {
+    Snapshot::read_from(text.as_bytes())
+}
+
+const SNAPSHOT: &str = "intcode-snapshot 3
+instruction_pointer 2
+relative_base 10
+instruction_count 1234
+input 1,2
+output 3
+memory 1101,0,3,1000,99
+page 1048576 7,8
+";
+
+#[test]
+fn read_snapshot() {
+    let snapshot = read(SNAPSHOT).unwrap();
+    assert_eq!(snapshot.instruction_pointer, 2);
+    assert_eq!(snapshot.relative_base, 10);
+    assert_eq!(snapshot.instruction_count, 1234);
+    assert_eq!(snapshot.input, [1, 2]);
+    assert_eq!(snapshot.output, [3]);
+    assert_eq!(snapshot.memory.get(4), 99);
+    assert_eq!(snapshot.memory.get(1048577), 8);
+
+    // Whole pages are written back.
+    let mut text = Vec::new();
+    snapshot.write_to(&mut text).unwrap();
+    assert_eq!(read(&String::from_utf8(text).unwrap()).unwrap(), snapshot);
+}
+
+#[test]
+fn read_version_1() {
+    let text = "intcode-snapshot 1
+instruction_pointer 2
+relative_base 10
+input 1,2
+output 3
+memory 1101,0,3,1000,99
+";
+    let snapshot = read(text).unwrap();
+    assert_eq!(snapshot.instruction_count, 0);
+    assert_eq!(snapshot.output, [3]);
+}
+
+#[test]
+fn read_version_2() {
+    let text = "intcode-snapshot 2
+instruction_pointer 2
+relative_base 10
+input 1,2
+output 3
+memory 1101,0,3,1000,99
+page 1048576 7,8
+";
+    let snapshot = read(text).unwrap();
+    assert_eq!(snapshot.instruction_count, 0);
+    assert_eq!(snapshot.memory.get(1048577), 8);
+
+    // A count is an error in version 2.
+    let text = SNAPSHOT.replacen("intcode-snapshot 3", "intcode-snapshot 2", 1);
+    assert!(matches!(read(&text), Err(SnapshotError::InvalidFormat(_))));
+}
+
+#[test]
+fn save_and_load() {
+    let program = program(9);
+    let mut expected = Machine::with_io(VecDeque::from(vec![2]), VecDeque::new());
+    expected.load_program(program.clone()).execute().unwrap();
+
+    let mut computer = Machine::with_io(VecDeque::from(vec![2]), VecDeque::new());
+    computer.load_program(program);
+    for _ in 0..1000 {
+        computer.step().unwrap();
+    }
+
+    let path = std::env::temp_dir().join(format!("snapshot-{}.txt", std::process::id()));
+    save_snapshot(&path, &computer.snapshot()).unwrap();
+    let snapshot = load_snapshot(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(snapshot, computer.snapshot());
+    assert_eq!(snapshot.instruction_count, 1000);
+
+    let mut restored = Machine::with_io(VecDeque::new(), VecDeque::new());
+    restored.restore(&snapshot);
+    assert_eq!(restored.instruction_count(), 1000);
+    assert_eq!(
+        restored.instruction_pointer(),
+        computer.instruction_pointer()
+    );
+    assert_eq!(restored.relative_base(), computer.relative_base());
+
+    restored.execute().unwrap();
+    assert_eq!(restored.output_device(), expected.output_device());
+    assert_eq!(restored.instruction_count(), expected.instruction_count());
+    assert_eq!(restored.memory(), expected.memory());
+}
+
+#[test]
+fn truncated_file() {
+    let text: String = SNAPSHOT
+        .lines()
+        .take(4)
+        .map(|line| line.to_string() + "\n")
+        .collect();
+    match read(&text) {
+        Err(SnapshotError::InvalidFormat(reason)) => assert_eq!(reason, "missing `input`"),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn bad_version() {
+    let text = SNAPSHOT.replacen("intcode-snapshot 3", "intcode-snapshot 99", 1);
+    match read(&text) {
+        Err(error @ SnapshotError::UnsupportedVersion(_)) => assert_eq!(
+            error.to_string(),
+            "unsupported snapshot version 99 (expected 1 to 3)"
+        ),
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    let text = SNAPSHOT.replacen("intcode-snapshot 3", "intcode-snapshot 0", 1);
+    assert!(matches!(
+        read(&text),
+        Err(SnapshotError::UnsupportedVersion(_))
+    ));
+}
+
+#[test]
+fn overflowing_page_address() {
+    let text = SNAPSHOT.replacen("page 1048576", &format!("page {}", usize::MAX), 1);
+    assert!(matches!(read(&text), Err(SnapshotError::InvalidFormat(_))));
+
+    let text = SNAPSHOT.replacen("page 1048576", &format!("page {}", isize::MAX), 1);
+    assert!(matches!(read(&text), Err(SnapshotError::InvalidFormat(_))));
+}
+
+#[test]
+fn restore_keeps_max_address() {
+    let mut computer = Machine::with_io(VecDeque::new(), VecDeque::new());
+    computer
+        .set_max_address(Some(100))
+        .load_program(vec![1101, 1, 1, 5000, 99]);
+    let mut text = Vec::new();
+    computer.snapshot().write_to(&mut text).unwrap();
+    let snapshot = Snapshot::read_from(&text[..]).unwrap();
+    assert_eq!(snapshot.memory.max_address(), None);
+
+    computer.restore(&snapshot);
+    assert_eq!(computer.memory().max_address(), Some(100));
+    assert_eq!(
+        computer.execute().unwrap_err().kind(),
+        IntCodeErrorKind::InvalidAddress(5000)
+    );
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use advent_of_code_2019::intcode::disasm::Operand;
+use advent_of_code_2019::intcode::{
+    CompiledProgram, Computer, MemoryWrite, Opcode, ParameterMode, Status, TraceRecord,
+    TracedOperand, Value,
+};
+use common::program;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+type Machine = Computer<VecDeque<Value>, Vec<Value>>;
+type Records = Arc<Mutex<Vec<TraceRecord>>>;
+
+fn traced(program: &[Value], input: &[Value]) -> (Machine, Records) {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let mut computer = Computer::with_io(input.iter().copied().collect(), Vec::new());
+    computer
+        .set_trace_sink(records.clone())
+        .load_program(program.to_vec());
+    (computer, records)
+}
+
+fn traced_operand(mode: ParameterMode, value: Value, resolved: Value) -> TracedOperand {
+    TracedOperand {
+        operand: Operand { mode, value },
+        resolved,
+    }
+}
+
+#[test]
+fn records() {
+    // add [9], #5, [9]; arb #2; out rb+7; hlt; data 10
+    let (mut computer, records) = traced(&[1001, 9, 5, 9, 109, 2, 204, 7, 99, 10], &[]);
+    computer.execute().unwrap();
+    assert_eq!(*computer.output_device(), [15]);
+
+    let records = records.lock().unwrap();
+    assert_eq!(
+        records[..],
+        [
+            TraceRecord {
+                instruction_pointer: 0,
+                relative_base: 0,
+                opcode: Opcode::Add,
+                operands: vec![
+                    traced_operand(ParameterMode::Position, 9, 10),
+                    traced_operand(ParameterMode::Immediate, 5, 5),
+                    // The address written to, not the value there.
+                    traced_operand(ParameterMode::Position, 9, 9),
+                ],
+                write: Some(MemoryWrite {
+                    address: 9,
+                    old: 10,
+                    new: 15,
+                }),
+            },
+            TraceRecord {
+                instruction_pointer: 4,
+                relative_base: 0,
+                opcode: Opcode::RelativeBaseOffset,
+                operands: vec![traced_operand(ParameterMode::Immediate, 2, 2)],
+                write: None,
+            },
+            TraceRecord {
+                instruction_pointer: 6,
+                relative_base: 2,
+                opcode: Opcode::Output,
+                operands: vec![traced_operand(ParameterMode::Relative, 7, 15)],
+                write: None,
+            },
+            TraceRecord {
+                instruction_pointer: 8,
+                relative_base: 2,
+                opcode: Opcode::Halt,
+                operands: Vec::new(),
+                write: None,
+            },
+        ]
+    );
+    assert_eq!(
+        records[0].to_string(),
+        "    0: add [9]=10, #5, [9] | rb=0 | [9]: 10 -> 15"
+    );
+}
+
+#[test]
+fn waiting_for_input_is_not_recorded() {
+    // in [3]; hlt; data 0
+    let (mut computer, records) = traced(&[3, 3, 99, 0], &[]);
+    assert_eq!(computer.run().unwrap(), Status::NeedsInput);
+    assert!(records.lock().unwrap().is_empty());
+
+    computer.input_device_mut().push_back(7);
+    assert_eq!(computer.run().unwrap(), Status::Halted);
+    let records = records.lock().unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(
+        records[0].write,
+        Some(MemoryWrite {
+            address: 3,
+            old: 0,
+            new: 7,
+        })
+    );
+}
+
+#[test]
+fn compiled_program_is_interpreted() {
+    let program = program(9);
+    let compiled = CompiledProgram::new(&program);
+    let (mut computer, records) = traced(&program, &[1]);
+    compiled.execute(&mut computer).unwrap();
+    assert_eq!(*computer.output_device(), [3409270027]);
+
+    // Every instruction is recorded, including the final halt, which does not
+    // count as executed.
+    let records = records.lock().unwrap();
+    assert_eq!(records.len() as u64, computer.instruction_count() + 1);
+    assert_eq!(records.last().unwrap().opcode, Opcode::Halt);
+}
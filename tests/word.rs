@@ -0,0 +1,68 @@
+use advent_of_code_2019::intcode::asm::assemble;
+use advent_of_code_2019::intcode::{Computer, IntCodeError, IntCodeErrorKind, Word};
+use num::BigInt;
+use std::collections::VecDeque;
+
+/// Read two values, then output their sum and product.
+const ARITHMETIC: &str = "
+    in [a]
+    in [b]
+    add [a], [b], [sum]
+    out [sum]
+    mul [a], [b], [product]
+    out [product]
+    hlt
+a: data 0
+b: data 0
+sum: data 0
+product: data 0
+";
+
+fn run<W: Word>(a: W, b: W) -> Result<Vec<W>, IntCodeError> {
+    let program = assemble(ARITHMETIC)
+        .unwrap()
+        .into_iter()
+        .map(W::from_value)
+        .collect();
+    let mut computer = Computer::<_, _, W>::with_devices(VecDeque::from(vec![a, b]), Vec::new());
+    computer.load_program(program).execute()?;
+    Ok(computer.output_device().clone())
+}
+
+#[test]
+fn add_overflow() {
+    let error = run(isize::MAX, 1).unwrap_err();
+    assert_eq!(error.kind(), IntCodeErrorKind::Overflow);
+    let error = run(i64::MIN, -1).unwrap_err();
+    assert_eq!(error.kind(), IntCodeErrorKind::Overflow);
+}
+
+#[test]
+fn mul_overflow() {
+    // The sum fits, the product does not.
+    let error = run(i64::MAX / 2, 3).unwrap_err();
+    assert_eq!(error.kind(), IntCodeErrorKind::Overflow);
+    let error = run(isize::MAX / 2, 3).unwrap_err();
+    assert_eq!(error.kind(), IntCodeErrorKind::Overflow);
+}
+
+#[test]
+fn wider_words() {
+    let max = i64::MAX as i128;
+    assert_eq!(run(max, 3).unwrap(), [max + 3, max * 3]);
+
+    let max = BigInt::from(isize::MAX);
+    let one = BigInt::from(1);
+    assert_eq!(run(max.clone(), one).unwrap(), [&max + 1, max.clone()]);
+
+    let max = BigInt::from(i64::MAX);
+    assert_eq!(
+        run(max.clone(), max.clone()).unwrap(),
+        [&max + &max, &max * &max]
+    );
+    let huge = BigInt::from(i128::MAX);
+    assert_eq!(
+        run(huge.clone(), huge.clone()).unwrap(),
+        [&huge + &huge, &huge * &huge]
+    );
+}